@@ -0,0 +1,275 @@
+//! Finds embedded files in any byte stream by their signatures alone, without relying on DAT tables.
+//!
+//! This is useful for damaged dumps where the tables can't be trusted, and for finding game data inside
+//! SOUND.DAT, the ELF or a whole disc image. Each candidate's length comes from its format's own size fields:
+//! the chunk size for RenderWare (checked against its child chunks), the chunk list for PNG, and the line counts of
+//! Put2D and Name scripts.
+//! Other scripts are text, so they end at the first null byte.
+
+use std::{
+    fmt,
+    io::{Error, Read, Seek, SeekFrom},
+};
+
+use crate::file::{detect::is_renderware_version, FileType};
+
+/// How much of the stream is scanned at a time
+const WINDOW_SIZE: usize = 0x10_0000;
+/// The longest signature prefix that needs to be visible past the end of a window
+const LOOKAHEAD: usize = 64;
+/// Scripts are never anywhere near this long, so a longer run of text is most likely not a script
+const MAX_SCRIPT_SIZE: u64 = 0x10_0000;
+const PNG_SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+/// The version every Anim animation stream starts with
+const ANIM_STREAM_VERSION: u32 = 0x100;
+
+/// A file found by its signature
+#[derive(Debug)]
+pub struct Carved {
+    /// The absolute offset of the file within the stream
+    pub offset: u64,
+    pub size: u64,
+    pub file_type: FileType,
+    /// The signature that was matched, such as `put2d-script` or `RenderWare chunk 0x10`
+    pub signature: String,
+}
+
+/// Sweeps the whole stream for known signatures. Candidates don't overlap; anything found inside an
+/// earlier candidate (like the child chunks of a RenderWare file) is skipped.
+pub fn carve<R: Read + Seek>(reader: &mut R) -> Result<Vec<Carved>, Error> {
+    let stream_size = reader.seek(SeekFrom::End(0))?;
+    let mut carved = vec![];
+    let mut position = 0;
+    while position < stream_size {
+        let window = read_at(reader, position, WINDOW_SIZE + LOOKAHEAD)?;
+        let scan_size = window.len().min(WINDOW_SIZE);
+        let mut next = position + scan_size as u64;
+        let mut i = 0;
+        while i < scan_size {
+            let offset = position + i as u64;
+            match identify(reader, offset, &window[i..])? {
+                Some(found) if offset + found.size <= stream_size => {
+                    let end = offset + found.size;
+                    carved.push(found);
+                    if end >= next {
+                        next = end;
+                        break;
+                    }
+                    i = (end - position) as usize;
+                }
+                _ => i += 1,
+            }
+        }
+        position = next;
+    }
+    Ok(carved)
+}
+
+/// Reads a candidate's data back out of the stream
+pub fn read_carved<R: Read + Seek>(reader: &mut R, carved: &Carved) -> Result<Vec<u8>, Error> {
+    read_at(reader, carved.offset, carved.size as usize)
+}
+
+/// Checks for a signature at the start of `data`, which begins at `offset` in the stream
+fn identify<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    data: &[u8],
+) -> Result<Option<Carved>, Error> {
+    let found = |size: u64, file_type: FileType, signature: String| {
+        Some(Carved {
+            offset,
+            size,
+            file_type,
+            signature,
+        })
+    };
+    Ok(match data.first() {
+        // RenderWare files only ever start on a four byte boundary
+        Some(0x10 | 0x16 | 0x1B | 0x1E) if offset & 3 == 0 => {
+            renderware_size(reader, offset, data)?.and_then(|(id, size)| {
+                let file_type = match id {
+                    0x10 => FileType::DFF,
+                    0x16 => FileType::TXD,
+                    0x1B => FileType::ANM,
+                    _ => FileType::DMA,
+                };
+                found(size, file_type, format!("RenderWare chunk {id:#04X}"))
+            })
+        }
+        Some(0x89) if data.starts_with(&PNG_SIGNATURE) => {
+            png_size(reader, offset)?.and_then(|size| found(size, FileType::PNG, "PNG".to_string()))
+        }
+        Some(b'p') if data.starts_with(b"put2d-script") => {
+            let text = read_script(reader, offset)?;
+            // the entry count is on line 5, followed by that many entries
+            found(
+                counted_lines(&text, 5),
+                FileType::PUT2D,
+                "put2d-script".to_string(),
+            )
+        }
+        Some(b'f') if data.starts_with(b"font-type") => {
+            let size = read_script(reader, offset)?.len() as u64;
+            found(size, FileType::FTI, "font-type".to_string())
+        }
+        Some(b'm') if data.starts_with(b"mif-") => match mif_kind(data) {
+            Some(kind) => {
+                let text = read_script(reader, offset)?;
+                let signature = format!("mif-{kind}-data-script");
+                match kind {
+                    // the name count is on line 2, followed by that many names
+                    "name" => found(counted_lines(&text, 2), FileType::NAME, signature),
+                    "fixed" => found(text.len() as u64, FileType::FIXED, signature),
+                    "attached" => found(text.len() as u64, FileType::ATTACHED, signature),
+                    "postbl" => found(text.len() as u64, FileType::POSTBL, signature),
+                    "comid" => found(text.len() as u64, FileType::COMID, signature),
+                    _ => found(
+                        text.len() as u64,
+                        FileType::OTHER {
+                            format: format!("MIF {kind} Data Script"),
+                            extension: format!(".{kind}"),
+                        },
+                        signature,
+                    ),
+                }
+            }
+            None => None,
+        },
+        _ => None,
+    })
+}
+
+/// The ID and total size of a plausible RenderWare chunk at the start of `data`, which begins at `offset`
+fn renderware_size<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    data: &[u8],
+) -> Result<Option<(u32, u64)>, Error> {
+    if data.len() < 24 {
+        return Ok(None);
+    }
+    let word = |i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+    let (id, size, version) = (word(0), word(4), word(8));
+    if !matches!(id, 0x10 | 0x16 | 0x1B | 0x1E) || !is_renderware_version(version) {
+        return Ok(None);
+    }
+    let valid = match id {
+        // clumps and texture dictionaries open with a struct chunk, and their children fill them exactly
+        0x10 | 0x16 => word(12) == 1 && children_fill(reader, offset, size, version)?,
+        // animations hold keyframes rather than chunks, but always start with the Anim stream version
+        0x1B => word(12) == ANIM_STREAM_VERSION,
+        // nothing is known about the layout of delta morph animations, so only their header is checked
+        _ => true,
+    };
+    Ok(valid.then_some((id, 12 + u64::from(size))))
+}
+
+/// Walks the children of a container chunk, which must all share its version and add up to its size. This is what
+/// keeps a plausible header in padding from claiming everything after it.
+fn children_fill<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    size: u32,
+    version: u32,
+) -> Result<bool, Error> {
+    let mut walked = 0;
+    while walked < u64::from(size) {
+        let header = read_at(reader, offset + 12 + walked, 12)?;
+        if header.len() < 12 {
+            return Ok(false);
+        }
+        let word = |i: usize| u32::from_le_bytes(header[i..i + 4].try_into().unwrap());
+        if word(8) != version {
+            return Ok(false);
+        }
+        walked += 12 + u64::from(word(4));
+    }
+    Ok(walked == u64::from(size))
+}
+
+/// Walks a PNG's chunks up to and including IEND
+fn png_size<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<Option<u64>, Error> {
+    let mut size = PNG_SIGNATURE.len() as u64;
+    loop {
+        let header = read_at(reader, offset + size, 8)?;
+        if header.len() < 8 {
+            return Ok(None);
+        }
+        let length = u32::from_be_bytes(header[0..4].try_into().unwrap());
+        if !header[4..8].iter().all(u8::is_ascii_alphabetic) {
+            return Ok(None);
+        }
+        // length, type, data and CRC
+        size += 12 + u64::from(length);
+        if &header[4..8] == b"IEND" {
+            return Ok(Some(size));
+        }
+    }
+}
+
+/// The kind from a `mif-<kind>-data-script` header
+fn mif_kind(data: &[u8]) -> Option<&str> {
+    let header = &data[4..];
+    let end = header.iter().position(|b| !b.is_ascii_lowercase())?;
+    if end > 0 && header[end..].starts_with(b"-data-script") {
+        std::str::from_utf8(&header[..end]).ok()
+    } else {
+        None
+    }
+}
+
+/// Reads a script up to the first null byte
+fn read_script<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<Vec<u8>, Error> {
+    let mut text = vec![];
+    let mut position = offset;
+    while (text.len() as u64) < MAX_SCRIPT_SIZE {
+        let chunk = read_at(reader, position, 0x1000)?;
+        if chunk.is_empty() {
+            break;
+        }
+        if let Some(end) = chunk.iter().position(|b| *b == 0) {
+            text.extend_from_slice(&chunk[..end]);
+            break;
+        }
+        position += chunk.len() as u64;
+        text.extend_from_slice(&chunk);
+    }
+    Ok(text)
+}
+
+/// The size of a script whose line `count_line` holds the number of lines that follow it
+fn counted_lines(text: &[u8], count_line: usize) -> u64 {
+    let count = text
+        .split(|b| *b == b'\n')
+        .nth(count_line)
+        .and_then(|l| std::str::from_utf8(l).ok())
+        .and_then(|l| l.trim_end_matches('\r').parse::<usize>().ok());
+    match count {
+        Some(count) => text
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| **b == b'\n')
+            .nth(count_line + count)
+            .map_or(text.len(), |(i, _)| i + 1) as u64,
+        // scripts that don't parse end at their first null byte instead
+        None => text.len() as u64,
+    }
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, offset: u64, size: usize) -> Result<Vec<u8>, Error> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut buffer = vec![];
+    reader.take(size as u64).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+impl fmt::Display for Carved {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:#010x} {} bytes: {}",
+            self.offset, self.size, self.signature
+        )
+    }
+}
@@ -0,0 +1,90 @@
+//! Converts files between the game's formats and documents that are easier to edit by hand.
+//!
+//! The direction is chosen from the extensions: a `.put2d` script becomes a `.json`, `.yaml` or `.yml` document, and
+//! a document becomes a `.put2d` script. Documents hold the header fields and every entry with its fields named,
+//! along with the original text of anything that wouldn't otherwise come back byte for byte, such as unknown entry
+//! types and malformed values. Fields the [`FieldSchema`] names are written under those names, with values it gives a
+//! meaning written as that meaning.
+
+use std::{fs, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    errors::ConvertError,
+    file::{put2d::Put2D, schema::FieldSchema},
+};
+
+/// A human-editable document format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Document {
+    Json,
+    Yaml,
+}
+
+impl Document {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match extension(path).as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    pub fn write<T: Serialize>(&self, value: &T) -> Result<String, ConvertError> {
+        Ok(match self {
+            Self::Json => serde_json::to_string_pretty(value)?,
+            Self::Yaml => serde_yaml::to_string(value)?,
+        })
+    }
+
+    pub fn read<T: DeserializeOwned>(&self, text: &str) -> Result<T, ConvertError> {
+        Ok(match self {
+            Self::Json => serde_json::from_str(text)?,
+            Self::Yaml => serde_yaml::from_str(text)?,
+        })
+    }
+}
+
+/// Converts `input` to `output` in whichever direction their extensions call for, naming fields with the builtin
+/// schema
+pub fn convert(input: &Path, output: &Path) -> Result<(), ConvertError> {
+    convert_with_schema(input, output, FieldSchema::builtin())
+}
+
+pub fn convert_with_schema(
+    input: &Path,
+    output: &Path,
+    schema: &FieldSchema,
+) -> Result<(), ConvertError> {
+    match (extension(input).as_str(), Document::from_path(output)) {
+        ("put2d", Some(document)) => {
+            let put2d_script = Put2D::from_data(&fs::read(input)?)?;
+            let mut value = serde_json::to_value(&put2d_script)?;
+            schema.export_put2d(&mut value);
+            fs::write(output, document.write(&value)?)?;
+        }
+        _ => match (Document::from_path(input), extension(output).as_str()) {
+            (Some(document), "put2d") => {
+                let mut value: Value = document.read(&fs::read_to_string(input)?)?;
+                schema.import_put2d(&mut value)?;
+                let put2d_script: Put2D = serde_json::from_value(value)?;
+                fs::write(output, put2d_script.to_bytes()?)?;
+            }
+            _ => {
+                return Err(ConvertError::Unsupported(
+                    input.display().to_string(),
+                    output.display().to_string(),
+                ))
+            }
+        },
+    }
+    Ok(())
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
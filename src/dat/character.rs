@@ -0,0 +1,175 @@
+use std::fmt;
+
+use super::{ArchiveType, InnerDAT, DAT};
+use crate::{
+    errors::ArchiveError,
+    file::{name::Name, File, FileType},
+};
+
+/// The index of the model within a character archive
+const MODEL_SLOT: usize = 0;
+/// The index of the NAME script; every file after it is an animation
+const NAME_SLOT: usize = 6;
+
+/// A [`ArchiveType::CHARACTER`] archive: a model, its textures, and animations named by a NAME script.
+///
+/// The animation after the NAME script is named by the script's first name, the next by its second, and so on.
+pub struct CharacterArchive<'a> {
+    inner_dat: &'a InnerDAT,
+    name: Name,
+}
+
+/// A way a character archive disagrees with its NAME script
+#[derive(Debug, PartialEq)]
+pub enum CharacterMismatch {
+    /// The NAME script lists a different number of names than there are slots after it
+    AnimationCount { names: usize, animations: usize },
+    /// A file in an animation slot that isn't an animation; it still takes the name at its position
+    NotAnimation { file: usize, file_type: String },
+}
+
+impl<'a> CharacterArchive<'a> {
+    pub fn new(dat: &DAT, inner_dat: &'a InnerDAT) -> Result<Self, ArchiveError> {
+        if inner_dat.archive_type != ArchiveType::CHARACTER {
+            return Err(ArchiveError::WrongType(inner_dat.archive_type));
+        }
+        let name_file = inner_dat
+            .files
+            .get(NAME_SLOT)
+            .ok_or_else(|| ArchiveError::MissingSlot("name".to_string()))?;
+        Ok(Self {
+            inner_dat,
+            name: name_file.decode(dat, inner_dat)?,
+        })
+    }
+
+    /// The model, if the archive's first file was recognized as one
+    pub fn model(&self) -> Option<&'a File> {
+        self.inner_dat
+            .files
+            .get(MODEL_SLOT)
+            .filter(|f| f.file_type == FileType::DFF)
+    }
+
+    /// The texture dictionaries stored between the model and the NAME script
+    pub fn textures(&self) -> impl Iterator<Item = &'a File> {
+        self.inner_dat.files[..NAME_SLOT]
+            .iter()
+            .filter(|f| f.file_type == FileType::TXD)
+    }
+
+    pub fn name_file(&self) -> &'a File {
+        &self.inner_dat.files[NAME_SLOT]
+    }
+
+    pub fn name_script(&self) -> &Name {
+        &self.name
+    }
+
+    /// The character's name, taken from the path the NAME script was built from
+    pub fn character_name(&self) -> Option<&str> {
+        self.name
+            .file_path
+            .split('/')
+            .nth(4)?
+            .strip_suffix(".name.out")
+    }
+
+    /// Every animation with the name the NAME script gives its slot, in archive order. Slots holding something other
+    /// than an animation are skipped without shifting the names of the rest, and animations beyond the end of the
+    /// script's names are left out; see [`CharacterArchive::mismatches`].
+    pub fn animations(&self) -> Vec<(&str, &'a File)> {
+        self.animation_slots()
+            .into_iter()
+            .filter(|(_, _, file)| is_animation(file))
+            .map(|(name, _, file)| (name, file))
+            .collect()
+    }
+
+    pub fn animation(&self, name: &str) -> Option<&'a File> {
+        self.animations()
+            .into_iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, file)| file)
+    }
+
+    /// Names the slots following the NAME script, in order
+    pub(super) fn file_names(&self) -> Vec<(usize, String)> {
+        self.animation_slots()
+            .into_iter()
+            .map(|(name, i, _)| (i, name.to_string()))
+            .collect()
+    }
+
+    /// Each slot after the NAME script with the name at the same position, along with its index in the archive
+    fn animation_slots(&self) -> Vec<(&str, usize, &'a File)> {
+        self.inner_dat
+            .files
+            .iter()
+            .enumerate()
+            .skip(NAME_SLOT + 1)
+            .zip(&self.name.names)
+            .map(|((i, file), name)| (name.as_str(), i, file))
+            .collect()
+    }
+
+    /// Everything about the archive that doesn't line up with the NAME script
+    pub fn mismatches(&self) -> Vec<CharacterMismatch> {
+        let slots = &self.inner_dat.files[NAME_SLOT + 1..];
+        let mut mismatches = vec![];
+        if slots.len() != self.name.names.len() {
+            mismatches.push(CharacterMismatch::AnimationCount {
+                names: self.name.names.len(),
+                animations: slots.len(),
+            });
+        }
+        for (i, file) in slots.iter().enumerate() {
+            if !is_animation(file) {
+                mismatches.push(CharacterMismatch::NotAnimation {
+                    file: NAME_SLOT + 1 + i,
+                    file_type: file.file_type.to_string(),
+                });
+            }
+        }
+        mismatches
+    }
+}
+
+/// Characters are animated by both skeletal and delta morph animations
+fn is_animation(file: &File) -> bool {
+    matches!(file.file_type, FileType::ANM | FileType::DMA)
+}
+
+impl fmt::Display for CharacterMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::AnimationCount { names, animations } => write!(
+                f,
+                "the NAME script lists {names} names, but there are {animations} animation slots"
+            ),
+            Self::NotAnimation { file, file_type } if file_type.is_empty() => {
+                write!(
+                    f,
+                    "file {file} is in an animation slot but wasn't recognized"
+                )
+            }
+            Self::NotAnimation { file, file_type } => {
+                write!(
+                    f,
+                    "file {file} is in an animation slot but is a {file_type} file"
+                )
+            }
+        }
+    }
+}
+
+impl DAT {
+    /// A typed view of the archive at `inner_index`, if it's a character archive
+    pub fn character(&self, inner_index: usize) -> Result<CharacterArchive<'_>, ArchiveError> {
+        let inner_dat = self
+            .inner_dats
+            .get(inner_index)
+            .ok_or(ArchiveError::MissingArchive(inner_index))?;
+        CharacterArchive::new(self, inner_dat)
+    }
+}
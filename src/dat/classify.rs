@@ -0,0 +1,116 @@
+use std::{collections::BTreeMap, fs, path::Path, sync::OnceLock};
+
+use serde::Deserialize;
+
+use super::{ArchiveType, InnerDAT, DAT};
+use crate::errors::RulesError;
+
+const BUILTIN_RULES: &str = include_str!("archive_rules.json");
+
+/// Decides an archive's [`ArchiveType`] from the types of the files it contains.
+///
+/// File types are written as the extension [`FileType`](crate::file::FileType)'s `Display` gives them, such as
+/// `.dff`, with `""` for files that weren't recognized. Every condition that's given must hold for the rule to match.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArchiveRule {
+    pub archive_type: ArchiveType,
+    /// Why archives matching the rule are given its type
+    #[serde(default)]
+    pub description: String,
+    /// The first file must be one of these types
+    #[serde(default)]
+    pub first: Vec<String>,
+    /// The file at each index must be of the given type
+    #[serde(default)]
+    pub at: BTreeMap<usize, String>,
+    /// Each of these types must appear somewhere in the archive
+    #[serde(default)]
+    pub contains: Vec<String>,
+    /// Every file must be one of these types
+    #[serde(default)]
+    pub only: Vec<String>,
+    #[serde(default = "default_min_files")]
+    pub min_files: usize,
+}
+
+fn default_min_files() -> usize {
+    1
+}
+
+impl ArchiveRule {
+    pub fn matches(&self, composition: &[String]) -> bool {
+        composition.len() >= self.min_files
+            && (self.first.is_empty()
+                || composition.first().is_some_and(|t| self.first.contains(t)))
+            && self
+                .at
+                .iter()
+                .all(|(i, file_type)| composition.get(*i) == Some(file_type))
+            && self.contains.iter().all(|t| composition.contains(t))
+            && (self.only.is_empty() || composition.iter().all(|t| self.only.contains(t)))
+    }
+}
+
+/// An ordered list of [`ArchiveRule`]s; the first rule that matches decides the type
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct ArchiveClassifier {
+    pub rules: Vec<ArchiveRule>,
+}
+
+impl Default for ArchiveClassifier {
+    fn default() -> Self {
+        Self::builtin().clone()
+    }
+}
+
+impl ArchiveClassifier {
+    /// The rules the toolbox classifies archives with unless told otherwise
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: OnceLock<ArchiveClassifier> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            serde_json::from_str(BUILTIN_RULES).expect("builtin archive rules are invalid")
+        })
+    }
+
+    /// Loads rules from a JSON file laid out like `src/dat/archive_rules.json`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RulesError> {
+        let path = path.as_ref();
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| RulesError::Invalid(path.display().to_string(), e.to_string()))
+    }
+
+    /// The first rule matching the archive, if any
+    pub fn rule_for(&self, inner_dat: &InnerDAT) -> Option<&ArchiveRule> {
+        let composition = inner_dat.composition();
+        self.rules.iter().find(|rule| rule.matches(&composition))
+    }
+
+    pub fn classify(&self, inner_dat: &InnerDAT) -> ArchiveType {
+        self.rule_for(inner_dat)
+            .map_or(ArchiveType::UNKNOWN, |rule| rule.archive_type)
+    }
+}
+
+impl InnerDAT {
+    /// The type of each file, as its extension
+    pub fn composition(&self) -> Vec<String> {
+        self.files.iter().map(|f| f.file_type.to_string()).collect()
+    }
+}
+
+impl DAT {
+    /// Classifies every archive again with a different set of rules, and renames them to match
+    pub fn reclassify(&mut self, classifier: &ArchiveClassifier) {
+        for i in 0..self.inner_dats.len() {
+            let archive_type = classifier.classify(&self.inner_dats[i]);
+            self.inner_dats[i].archive_type = archive_type;
+            let file_types = self.inner_dats[i].file_types(self);
+            self.inner_dats[i].retype_files(file_types);
+            let file_names = self.inner_dats[i].file_names(self);
+            self.inner_dats[i].name_files(file_names);
+            let archive_name = self.inner_dats[i].get_names(self);
+            self.inner_dats[i].archive_name = archive_name;
+        }
+    }
+}
@@ -0,0 +1,519 @@
+use std::{collections::HashMap, fmt, io::Error};
+
+use super::{InnerDAT, DAT};
+use crate::file::{
+    decode::Decode,
+    name::Name,
+    put2d::Put2D,
+    schema::{FieldSchema, NamedField},
+    File, FileType,
+};
+
+/// Everything that differs between two DATs
+#[derive(Debug)]
+pub struct DatDiff {
+    /// Only archives that changed are listed
+    pub archives: Vec<ArchiveDiff>,
+}
+
+#[derive(Debug)]
+pub struct ArchiveDiff {
+    pub old_index: Option<usize>,
+    pub new_index: Option<usize>,
+    pub archive_name: Option<String>,
+    pub change: ArchiveChange,
+}
+
+#[derive(Debug)]
+pub enum ArchiveChange {
+    Added,
+    Removed,
+    /// Only files that changed are listed
+    Modified(Vec<FileDiff>),
+}
+
+#[derive(Debug)]
+pub struct FileDiff {
+    pub old_index: Option<usize>,
+    pub new_index: Option<usize>,
+    pub file_name: Option<String>,
+    pub change: FileChange,
+    /// Field level changes, for file types the toolbox can parse
+    pub details: Vec<Detail>,
+}
+
+#[derive(Debug)]
+pub enum FileChange {
+    Added {
+        size: u32,
+    },
+    Removed {
+        size: u32,
+    },
+    Resized {
+        old_size: u32,
+        new_size: u32,
+    },
+    /// Same size, different contents
+    Changed,
+}
+
+#[derive(Debug)]
+pub enum Detail {
+    PlayerAdded(String),
+    PlayerRemoved(String),
+    StatsField {
+        player: String,
+        field: String,
+        old: String,
+        new: String,
+    },
+    Put2DHeader {
+        field: &'static str,
+        old: String,
+        new: String,
+    },
+    /// A field of a Put2D entry that kept its type
+    Put2DField {
+        index: usize,
+        field: String,
+        old: String,
+        new: String,
+    },
+    /// A Put2D entry that was added (`old` is `None`), removed (`new` is `None`) or changed type
+    Put2DEntry {
+        index: usize,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    NamePath {
+        old: String,
+        new: String,
+    },
+    NameAdded(String),
+    NameRemoved(String),
+}
+
+impl DAT {
+    /// Compares this DAT against another, pairing archives and files by name where possible and by index otherwise
+    pub fn diff(&self, other: &DAT) -> Result<DatDiff, Error> {
+        self.diff_with_schema(other, FieldSchema::builtin())
+    }
+
+    /// Compares this DAT against another, naming changed fields with a custom schema
+    pub fn diff_with_schema(&self, other: &DAT, schema: &FieldSchema) -> Result<DatDiff, Error> {
+        let mut archives = vec![];
+        let pairs = align(
+            self.inner_dats.iter().map(|i| i.archive_name.as_deref()),
+            other.inner_dats.iter().map(|i| i.archive_name.as_deref()),
+        );
+        for (old_index, new_index) in pairs {
+            let old = old_index.map(|i| &self.inner_dats[i]);
+            let new = new_index.map(|i| &other.inner_dats[i]);
+            let change = match (old, new) {
+                (Some(old), Some(new)) => {
+                    let files = diff_archive(self, old, other, new, schema)?;
+                    if files.is_empty() {
+                        continue;
+                    }
+                    ArchiveChange::Modified(files)
+                }
+                (None, _) => ArchiveChange::Added,
+                (_, None) => ArchiveChange::Removed,
+            };
+            archives.push(ArchiveDiff {
+                old_index,
+                new_index,
+                archive_name: new.or(old).and_then(|i| i.archive_name.clone()),
+                change,
+            });
+        }
+        Ok(DatDiff { archives })
+    }
+}
+
+fn diff_archive(
+    old_dat: &DAT,
+    old: &InnerDAT,
+    new_dat: &DAT,
+    new: &InnerDAT,
+    schema: &FieldSchema,
+) -> Result<Vec<FileDiff>, Error> {
+    let mut files = vec![];
+    let pairs = align(
+        old.files.iter().map(|f| f.file_name.as_deref()),
+        new.files.iter().map(|f| f.file_name.as_deref()),
+    );
+    for (old_index, new_index) in pairs {
+        let old_file = old_index.map(|i| &old.files[i]);
+        let new_file = new_index.map(|i| &new.files[i]);
+        let (change, details) = match (old_file, new_file) {
+            (Some(old_file), Some(new_file)) => {
+                let old_data = old_dat.read_file(old, old_file)?;
+                let new_data = new_dat.read_file(new, new_file)?;
+                if old_data == new_data {
+                    continue;
+                }
+                let change = if old_data.len() == new_data.len() {
+                    FileChange::Changed
+                } else {
+                    FileChange::Resized {
+                        old_size: old_file.size,
+                        new_size: new_file.size,
+                    }
+                };
+                (
+                    change,
+                    diff_contents(old_file, &old_data, new_file, &new_data, schema),
+                )
+            }
+            (None, Some(new_file)) => (
+                FileChange::Added {
+                    size: new_file.size,
+                },
+                vec![],
+            ),
+            (Some(old_file), None) => (
+                FileChange::Removed {
+                    size: old_file.size,
+                },
+                vec![],
+            ),
+            (None, None) => continue,
+        };
+        files.push(FileDiff {
+            old_index,
+            new_index,
+            file_name: new_file.or(old_file).and_then(|f| f.file_name.clone()),
+            change,
+            details,
+        });
+    }
+    Ok(files)
+}
+
+fn diff_contents(
+    old: &File,
+    old_data: &[u8],
+    new: &File,
+    new_data: &[u8],
+    schema: &FieldSchema,
+) -> Vec<Detail> {
+    match (&old.file_type, &new.file_type) {
+        (FileType::STATS, FileType::STATS) => diff_stats(old_data, new_data, schema),
+        (FileType::PUT2D, FileType::PUT2D) => {
+            match (Put2D::decode(old_data), Put2D::decode(new_data)) {
+                (Ok(old), Ok(new)) => diff_put2d(&old, &new, schema),
+                _ => vec![],
+            }
+        }
+        (FileType::NAME, FileType::NAME) => {
+            let (Ok(old), Ok(new)) = (Name::decode(old_data), Name::decode(new_data)) else {
+                return vec![];
+            };
+            let mut details = vec![];
+            if old.file_path != new.file_path {
+                details.push(Detail::NamePath {
+                    old: old.file_path.clone(),
+                    new: new.file_path.clone(),
+                });
+            }
+            for name in old.names.iter().filter(|n| !new.names.contains(n)) {
+                details.push(Detail::NameRemoved(name.clone()));
+            }
+            for name in new.names.iter().filter(|n| !old.names.contains(n)) {
+                details.push(Detail::NameAdded(name.clone()));
+            }
+            details
+        }
+        _ => vec![],
+    }
+}
+
+/// Compares stats files column by column, so fields the parser doesn't understand are still reported
+fn diff_stats(old_data: &[u8], new_data: &[u8], schema: &FieldSchema) -> Vec<Detail> {
+    let columns = |data: &[u8]| -> Vec<Vec<NamedField>> {
+        String::from_utf8_lossy(data)
+            .split('\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| schema.stats_fields(line))
+            .collect()
+    };
+    let old_players = columns(old_data);
+    let new_players = columns(new_data);
+    let mut details = vec![];
+    let pairs = align(
+        old_players.iter().map(|p| Some(p[0].value.as_str())),
+        new_players.iter().map(|p| Some(p[0].value.as_str())),
+    );
+    for (old_index, new_index) in pairs {
+        match (old_index, new_index) {
+            (Some(o), Some(n)) => {
+                let (old, new) = (&old_players[o], &new_players[n]);
+                details.extend(diff_fields(old, new).into_iter().map(|(field, old, new)| {
+                    Detail::StatsField {
+                        player: new_players[n][0].value.clone(),
+                        field,
+                        old,
+                        new,
+                    }
+                }));
+            }
+            (Some(o), None) => details.push(Detail::PlayerRemoved(old_players[o][0].value.clone())),
+            (None, Some(n)) => details.push(Detail::PlayerAdded(new_players[n][0].value.clone())),
+            (None, None) => {}
+        }
+    }
+    details
+}
+
+fn diff_put2d(old: &Put2D, new: &Put2D, schema: &FieldSchema) -> Vec<Detail> {
+    let mut details = vec![];
+    let headers: [(&'static str, String, String); 4] = [
+        ("id", old.id.to_string(), new.id.to_string()),
+        ("unknown", old.unknown.to_string(), new.unknown.to_string()),
+        ("txd_path", old.txd_path.clone(), new.txd_path.clone()),
+        ("txt_path", old.txt_path.clone(), new.txt_path.clone()),
+    ];
+    let (old_header, new_header) = (old.header_text(), new.header_text());
+    for (line, (field, old, new)) in headers.into_iter().enumerate() {
+        let (old_text, new_text) = (&old_header[line + 1], &new_header[line + 1]);
+        if old != new {
+            details.push(Detail::Put2DHeader { field, old, new });
+        } else if old_text != new_text {
+            // the same value written differently, like `007` for `7`
+            details.push(Detail::Put2DHeader {
+                field,
+                old: old_text.clone(),
+                new: new_text.clone(),
+            });
+        }
+    }
+    for index in 0..old.entries.len().max(new.entries.len()) {
+        let old_entry = old.entries.get(index);
+        let new_entry = new.entries.get(index);
+        if let (Some(old_entry), Some(new_entry)) = (old_entry, new_entry) {
+            if old_entry.entry_type.number() == new_entry.entry_type.number() {
+                let old_fields = schema.put2d_fields(old_entry);
+                let new_fields = schema.put2d_fields(new_entry);
+                let fields = match diff_fields(&old_fields, &new_fields) {
+                    fields if !fields.is_empty() => Some(fields),
+                    // the same values written differently, like `012` for `12`
+                    _ => diff_tokens(&old_entry.to_string(), &new_entry.to_string(), &new_fields),
+                };
+                if let Some(fields) = fields {
+                    details.extend(fields.into_iter().map(|(field, old, new)| {
+                        Detail::Put2DField {
+                            index,
+                            field,
+                            old,
+                            new,
+                        }
+                    }));
+                    continue;
+                }
+            }
+        }
+        if old_entry != new_entry {
+            details.push(Detail::Put2DEntry {
+                index,
+                old: old_entry.map(|e| e.to_string()),
+                new: new_entry.map(|e| e.to_string()),
+            });
+        }
+    }
+    details
+}
+
+/// The name, old value and new value of every field that differs, with fields missing on one side shown as empty
+fn diff_fields(old: &[NamedField], new: &[NamedField]) -> Vec<(String, String, String)> {
+    let value = |field: Option<&NamedField>| field.map_or_else(String::new, value_text);
+    (0..old.len().max(new.len()))
+        .filter(|&i| old.get(i).map(|f| &f.value) != new.get(i).map(|f| &f.value))
+        .map(|i| {
+            let name = new
+                .get(i)
+                .or(old.get(i))
+                .map(|f| f.name.clone())
+                .unwrap_or_default();
+            (name, value(old.get(i)), value(new.get(i)))
+        })
+        .collect()
+}
+
+/// The tokens written differently on two lines, named after the field at the same position. `None` if the lines
+/// don't have the same number of tokens.
+fn diff_tokens(
+    old: &str,
+    new: &str,
+    fields: &[NamedField],
+) -> Option<Vec<(String, String, String)>> {
+    let old: Vec<&str> = old.split(' ').collect();
+    let new: Vec<&str> = new.split(' ').collect();
+    if old.len() != new.len() {
+        return None;
+    }
+    let name = |position: usize| match fields.iter().find(|f| f.position == position) {
+        Some(field) => field.name.clone(),
+        None if position == 1 => "type".to_string(),
+        None => format!("field_{position}"),
+    };
+    Some(
+        old.iter()
+            .zip(&new)
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(position, (old, new))| (name(position), old.to_string(), new.to_string()))
+            .collect(),
+    )
+}
+
+/// A field's value with its unit and meaning, without its name
+fn value_text(field: &NamedField) -> String {
+    let mut text = field.value.clone();
+    if let Some(unit) = &field.unit {
+        text = format!("{text} {unit}");
+    }
+    if let Some(meaning) = &field.meaning {
+        text = format!("{text} ({meaning})");
+    }
+    text
+}
+
+/// Pairs up two lists, matching names that are unique on both sides first and falling back to matching indices
+fn align<'a>(
+    old: impl Iterator<Item = Option<&'a str>>,
+    new: impl Iterator<Item = Option<&'a str>>,
+) -> Vec<(Option<usize>, Option<usize>)> {
+    let old: Vec<Option<&str>> = old.collect();
+    let new: Vec<Option<&str>> = new.collect();
+    let unique = |names: &[Option<&'a str>]| -> HashMap<&'a str, usize> {
+        let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+        for (i, name) in names.iter().enumerate() {
+            if let Some(name) = name {
+                counts.entry(name).or_insert((i, 0)).1 += 1;
+            }
+        }
+        counts
+            .into_iter()
+            .filter(|(_, (_, count))| *count == 1)
+            .map(|(name, (i, _))| (name, i))
+            .collect()
+    };
+    let old_unique = unique(&old);
+    let new_unique = unique(&new);
+
+    let mut new_for_old: Vec<Option<usize>> = vec![None; old.len()];
+    let mut old_for_new: Vec<Option<usize>> = vec![None; new.len()];
+    for (name, &o) in &old_unique {
+        if let Some(&n) = new_unique.get(name) {
+            new_for_old[o] = Some(n);
+            old_for_new[n] = Some(o);
+        }
+    }
+    for i in 0..old.len().min(new.len()) {
+        if new_for_old[i].is_none() && old_for_new[i].is_none() {
+            new_for_old[i] = Some(i);
+            old_for_new[i] = Some(i);
+        }
+    }
+
+    let mut pairs: Vec<(Option<usize>, Option<usize>)> = new_for_old
+        .iter()
+        .enumerate()
+        .map(|(o, n)| (Some(o), *n))
+        .collect();
+    pairs.extend(
+        old_for_new
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| o.is_none())
+            .map(|(n, _)| (None, Some(n))),
+    );
+    pairs.sort_by_key(|(o, n)| (n.or(*o), o.is_some()));
+    pairs
+}
+
+impl fmt::Display for DatDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.archives.is_empty() {
+            return writeln!(f, "No differences");
+        }
+        for archive in &self.archives {
+            let label = label(archive.old_index, archive.new_index, &archive.archive_name);
+            match &archive.change {
+                ArchiveChange::Added => writeln!(f, "+ archive {label}")?,
+                ArchiveChange::Removed => writeln!(f, "- archive {label}")?,
+                ArchiveChange::Modified(files) => {
+                    writeln!(f, "~ archive {label}")?;
+                    for file in files {
+                        write!(f, "{file}")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FileDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = label(self.old_index, self.new_index, &self.file_name);
+        match self.change {
+            FileChange::Added { size } => writeln!(f, "    + {label} ({size} bytes)")?,
+            FileChange::Removed { size } => writeln!(f, "    - {label} ({size} bytes)")?,
+            FileChange::Resized { old_size, new_size } => {
+                writeln!(f, "    ~ {label} resized {old_size} -> {new_size} bytes")?
+            }
+            FileChange::Changed => writeln!(f, "    ~ {label} changed")?,
+        }
+        for detail in &self.details {
+            writeln!(f, "        {detail}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Detail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PlayerAdded(player) => write!(f, "+ player {player}"),
+            Self::PlayerRemoved(player) => write!(f, "- player {player}"),
+            Self::StatsField {
+                player,
+                field,
+                old,
+                new,
+            } => write!(f, "{player}.{field}: {old} -> {new}"),
+            Self::Put2DHeader { field, old, new } => write!(f, "{field}: {old} -> {new}"),
+            Self::Put2DField {
+                index,
+                field,
+                old,
+                new,
+            } => write!(f, "entry {index}.{field}: {old} -> {new}"),
+            Self::Put2DEntry { index, old, new } => match (old, new) {
+                (Some(old), Some(new)) => write!(f, "entry {index}: {old} -> {new}"),
+                (None, Some(new)) => write!(f, "+ entry {index}: {new}"),
+                (Some(old), None) => write!(f, "- entry {index}: {old}"),
+                (None, None) => Ok(()),
+            },
+            Self::NamePath { old, new } => write!(f, "file_path: {old} -> {new}"),
+            Self::NameAdded(name) => write!(f, "+ name {name}"),
+            Self::NameRemoved(name) => write!(f, "- name {name}"),
+        }
+    }
+}
+
+/// Describes an item by its index (or old -> new indices) and name
+fn label(old_index: Option<usize>, new_index: Option<usize>, name: &Option<String>) -> String {
+    let index = match (old_index, new_index) {
+        (Some(o), Some(n)) if o != n => format!("{o} -> {n}"),
+        (_, Some(i)) | (Some(i), None) => i.to_string(),
+        (None, None) => String::new(),
+    };
+    match name {
+        Some(name) => format!("{index} ({name})"),
+        None => index,
+    }
+}
@@ -0,0 +1,132 @@
+use std::{collections::HashMap, fmt, io::Error};
+
+use crc32fast::hash;
+
+use super::DAT;
+
+/// Files with identical contents
+#[derive(Debug)]
+pub struct DuplicateGroup {
+    pub size: u32,
+    pub crc32: u32,
+    /// The InnerDAT and file index of every copy, in table order
+    pub files: Vec<(usize, usize)>,
+}
+
+impl DuplicateGroup {
+    /// The bytes taken up by every copy after the first
+    pub fn wasted(&self) -> u64 {
+        u64::from(self.size) * (self.files.len() as u64 - 1)
+    }
+
+    /// The bytes [`EditSession::share_duplicates`](super::session::EditSession::share_duplicates) saves, which only
+    /// shares copies within the same InnerDAT
+    pub fn recoverable(&self) -> u64 {
+        let mut archives: Vec<usize> = self.files.iter().map(|(i, _)| *i).collect();
+        archives.dedup();
+        u64::from(self.size) * (self.files.len() - archives.len()) as u64
+    }
+}
+
+/// Every set of files in a DAT that share the same contents
+#[derive(Debug)]
+pub struct DuplicateReport {
+    /// Sorted by wasted bytes, largest first
+    pub groups: Vec<DuplicateGroup>,
+}
+
+impl DuplicateReport {
+    pub fn wasted(&self) -> u64 {
+        self.groups.iter().map(DuplicateGroup::wasted).sum()
+    }
+
+    pub fn recoverable(&self) -> u64 {
+        self.groups.iter().map(DuplicateGroup::recoverable).sum()
+    }
+
+    /// The other copies of a file, if it has any
+    pub fn duplicates_of(&self, inner_index: usize, file_index: usize) -> Vec<(usize, usize)> {
+        let file = (inner_index, file_index);
+        self.groups
+            .iter()
+            .find(|group| group.files.contains(&file))
+            .map(|group| group.files.iter().copied().filter(|f| *f != file).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl DAT {
+    /// Hashes every file and groups the ones with identical contents. Empty files are ignored.
+    pub fn find_duplicates(&self) -> Result<DuplicateReport, Error> {
+        // files are only compared byte for byte when their size and CRC32 match
+        let mut candidates: HashMap<(u32, u32), Vec<(usize, usize)>> = HashMap::new();
+        for (i, inner_dat) in self.inner_dats.iter().enumerate() {
+            for (j, file) in inner_dat.files.iter().enumerate() {
+                if file.size == 0 {
+                    continue;
+                }
+                let crc32 = hash(&self.read_file(inner_dat, file)?);
+                candidates
+                    .entry((file.size, crc32))
+                    .or_default()
+                    .push((i, j));
+            }
+        }
+
+        let mut groups = vec![];
+        for ((size, crc32), files) in candidates.into_iter().filter(|(_, f)| f.len() > 1) {
+            // one group per distinct content, in case different contents collide
+            let mut contents: Vec<Vec<u8>> = vec![];
+            let mut distinct: Vec<DuplicateGroup> = vec![];
+            for (i, j) in files {
+                let inner_dat = &self.inner_dats[i];
+                let data = self.read_file(inner_dat, &inner_dat.files[j])?;
+                match contents.iter().position(|d| *d == data) {
+                    Some(k) => distinct[k].files.push((i, j)),
+                    None => {
+                        contents.push(data);
+                        distinct.push(DuplicateGroup {
+                            size,
+                            crc32,
+                            files: vec![(i, j)],
+                        });
+                    }
+                }
+            }
+            groups.extend(distinct.into_iter().filter(|g| g.files.len() > 1));
+        }
+        groups.sort_by(|a, b| b.wasted().cmp(&a.wasted()).then(a.files.cmp(&b.files)));
+        Ok(DuplicateReport { groups })
+    }
+}
+
+impl fmt::Display for DuplicateGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let files: Vec<String> = self.files.iter().map(|(i, j)| format!("{i}-{j}")).collect();
+        write!(
+            f,
+            "{} copies of {} bytes (CRC32 {:08x}, {} bytes wasted, {} recoverable): {}",
+            self.files.len(),
+            self.size,
+            self.crc32,
+            self.wasted(),
+            self.recoverable(),
+            files.join(", ")
+        )
+    }
+}
+
+impl fmt::Display for DuplicateReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for group in &self.groups {
+            writeln!(f, "{group}")?;
+        }
+        writeln!(
+            f,
+            "{} groups of duplicates, {} bytes wasted, {} recoverable by sharing within an InnerDAT",
+            self.groups.len(),
+            self.wasted(),
+            self.recoverable()
+        )
+    }
+}
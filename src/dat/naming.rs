@@ -0,0 +1,98 @@
+use super::{InnerDAT, DAT};
+use crate::{
+    file::{
+        mif::MifScript,
+        name::Name,
+        renderware::{Dff, Txd},
+    },
+    manifest::Manifest,
+};
+
+impl InnerDAT {
+    /// Derives a name from the names embedded in the archive's files, trying each source across every file before
+    /// moving on to the next: model frame names, then dictionary texture names, then the textures the model's
+    /// materials use, then paths embedded in scripts.
+    pub(super) fn embedded_name(&self, dat: &DAT) -> Option<String> {
+        let dffs: Vec<Dff> = self
+            .files
+            .iter()
+            .filter_map(|f| f.decode(dat, self).ok())
+            .collect();
+        let txds: Vec<Txd> = self
+            .files
+            .iter()
+            .filter_map(|f| f.decode(dat, self).ok())
+            .collect();
+        let candidates = dffs
+            .iter()
+            .flat_map(Dff::frame_names)
+            .chain(txds.iter().flat_map(Txd::texture_names))
+            .chain(dffs.iter().flat_map(Dff::texture_names))
+            .chain(
+                std::iter::once_with(|| self.script_paths(dat))
+                    .flatten()
+                    .map(|path| base_name(&path)),
+            );
+        candidates.filter_map(|name| sanitize(&name)).next()
+    }
+
+    /// Paths embedded in MIF and NAME scripts
+    fn script_paths(&self, dat: &DAT) -> Vec<String> {
+        let mut paths = vec![];
+        for file in &self.files {
+            if let Ok(script) = file.decode::<MifScript>(dat, self) {
+                paths.extend(script.paths().into_iter().map(str::to_string));
+            } else if let Ok(name) = file.decode::<Name>(dat, self) {
+                paths.push(name.file_path);
+            }
+        }
+        paths
+    }
+}
+
+impl DAT {
+    /// Names every archive that's still unnamed after the names embedded in its files. This decodes every model,
+    /// texture dictionary and script in those archives, so it's left to callers that want the names rather than done
+    /// when the DAT is read.
+    pub fn name_archives(&mut self) {
+        for i in 0..self.inner_dats.len() {
+            if self.inner_dats[i].archive_name.is_none() {
+                let archive_name = self.inner_dats[i].embedded_name(self);
+                self.inner_dats[i].archive_name = archive_name;
+            }
+        }
+    }
+
+    /// Renames archives after a name table, which takes precedence over every other source of names. Any manifest
+    /// can be used as a name table; archives it doesn't name are left alone.
+    pub fn apply_names(&mut self, names: &Manifest) {
+        for (i, inner_dat) in self.inner_dats.iter_mut().enumerate() {
+            if let Some(name) = names.archive_name(i, inner_dat.files.len()) {
+                inner_dat.archive_name = Some(name.to_string());
+            }
+        }
+    }
+}
+
+/// The file name from a path, without any extensions
+fn base_name(path: &str) -> String {
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    file_name.split('.').next().unwrap_or(file_name).to_string()
+}
+
+/// Makes a name safe to use in a file name, or rejects it if nothing usable is left
+fn sanitize(name: &str) -> Option<String> {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = name.trim_matches('_');
+    (!name.is_empty()).then(|| name.to_string())
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{Error, ErrorKind, Read, Seek, SeekFrom, Write},
+};
+
+use super::{InnerDAT, DAT};
+
+/// The largest alignment assumed for InnerDATs within the primary DAT (one CD sector)
+pub(super) const MAX_ARCHIVE_ALIGNMENT: u32 = 0x800;
+/// The largest alignment assumed for files within an InnerDAT
+pub(super) const MAX_FILE_ALIGNMENT: u32 = 0x800;
+
+/// Describes how a replacement file is written into the DAT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchStrategy {
+    /// The replacement fits in the original slot (including its padding) and is written over it
+    InPlace {
+        /// The number of bytes available to the file before the next file (or InnerDAT) begins
+        capacity: u32,
+    },
+    /// The InnerDAT holding the file is rebuilt and appended to the end of the DAT
+    Relocate {
+        /// The address the rebuilt InnerDAT is written to
+        new_offset: u32,
+        /// The size of the rebuilt InnerDAT
+        new_size: u32,
+    },
+}
+
+impl fmt::Display for PatchStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InPlace { capacity } => {
+                write!(f, "in place (slot capacity {capacity:#X} bytes)")
+            }
+            Self::Relocate {
+                new_offset,
+                new_size,
+            } => write!(
+                f,
+                "relocate InnerDAT to {new_offset:#X} ({new_size:#X} bytes)"
+            ),
+        }
+    }
+}
+
+impl DAT {
+    /// Determines which strategy [`DAT::patch_file`] would use to write a replacement of `len` bytes, without writing anything
+    pub fn plan_patch(
+        &self,
+        inner_index: usize,
+        file_index: usize,
+        len: usize,
+    ) -> Result<PatchStrategy, Error> {
+        let inner_dat = self.get_inner(inner_index)?;
+        let capacity = self.slot_capacity(inner_dat, file_index)?;
+        if len <= capacity as usize && !is_shared(inner_dat, file_index) {
+            return Ok(PatchStrategy::InPlace { capacity });
+        }
+        let new_size = self.relocated_size(inner_dat, file_index, len)?;
+        Ok(PatchStrategy::Relocate {
+            new_offset: self.append_offset()?,
+            new_size,
+        })
+    }
+
+    /// Replaces a file's contents, patching the DAT in place when the data fits in the original slot.
+    /// Otherwise, or when other files share the slot's payload, only the affected InnerDAT is rebuilt and moved to
+    /// the end of the DAT.
+    ///
+    /// The DAT must have been opened with write access.
+    pub fn patch_file(
+        &mut self,
+        inner_index: usize,
+        file_index: usize,
+        data: &[u8],
+    ) -> Result<PatchStrategy, Error> {
+        let strategy = self.plan_patch(inner_index, file_index, data.len())?;
+        let inner_dat = &self.inner_dats[inner_index];
+        let mut io_file = &self.file;
+        let mut entry = self.entry_bytes(inner_index);
+        match strategy {
+            PatchStrategy::InPlace { .. } => {
+                let file = &inner_dat.files[file_index];
+                let size = to_u32(data.len())?;
+                io_file.seek(SeekFrom::Start((inner_dat.offset + file.offset).into()))?;
+                io_file.write_all(data)?;
+                // blank out whatever is left of the previous contents so the slot stays padded
+                if file.size > size {
+                    io_file.write_all(&vec![0; (file.size - size) as usize])?;
+                }
+                io_file.seek(SeekFrom::Start(
+                    (inner_dat.offset + 8 + 8 * to_u32(file_index)?).into(),
+                ))?;
+                io_file.write_all(&size.to_le_bytes())?;
+                let end = file.offset + size;
+                if end > inner_dat.size {
+                    // the last file grew into the padding after the InnerDAT
+                    entry[4..8].copy_from_slice(&end.to_le_bytes());
+                    self.write_entry(inner_index, &entry)?;
+                }
+            }
+            PatchStrategy::Relocate {
+                new_offset,
+                new_size,
+            } => {
+                let rebuilt = self.rebuild_inner(inner_dat, file_index, data)?;
+                debug_assert_eq!(rebuilt.len(), new_size as usize);
+                io_file.seek(SeekFrom::End(0))?;
+                let end = io_file.stream_position()?;
+                io_file.write_all(&vec![0; (u64::from(new_offset) - end) as usize])?;
+                io_file.write_all(&rebuilt)?;
+                entry[0..4].copy_from_slice(&new_offset.to_le_bytes());
+                entry[4..8].copy_from_slice(&new_size.to_le_bytes());
+                self.write_entry(inner_index, &entry)?;
+            }
+        }
+        io_file.flush()?;
+        self.inner_dats[inner_index] = InnerDAT::new(self, entry);
+        Ok(strategy)
+    }
+
+    fn get_inner(&self, inner_index: usize) -> Result<&InnerDAT, Error> {
+        self.inner_dats.get(inner_index).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("InnerDAT {inner_index} does not exist"),
+            )
+        })
+    }
+
+    /// The bytes available to a file before the next file, or the next InnerDAT, begins
+    fn slot_capacity(&self, inner_dat: &InnerDAT, file_index: usize) -> Result<u32, Error> {
+        let file = inner_dat.files.get(file_index).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("file {file_index} does not exist"),
+            )
+        })?;
+        let next_file = inner_dat
+            .files
+            .iter()
+            .map(|f| f.offset)
+            .filter(|&offset| offset > file.offset)
+            .min();
+        let limit = match next_file {
+            Some(offset) => offset,
+            None => {
+                // the last file may grow into the padding before the next InnerDAT
+                let next_inner = self
+                    .inner_dats
+                    .iter()
+                    .map(|i| i.offset)
+                    .filter(|&offset| offset > inner_dat.offset)
+                    .min();
+                match next_inner {
+                    Some(offset) => offset - inner_dat.offset,
+                    None => {
+                        let file_end = (&self.file).seek(SeekFrom::End(0))?;
+                        to_u32((file_end - u64::from(inner_dat.offset)) as usize)?
+                    }
+                }
+            }
+        };
+        Ok(limit.saturating_sub(file.offset))
+    }
+
+    /// The size an InnerDAT would have once rebuilt with a replacement of `len` bytes
+    fn relocated_size(
+        &self,
+        inner_dat: &InnerDAT,
+        file_index: usize,
+        len: usize,
+    ) -> Result<u32, Error> {
+        let layout = relocated_layout(inner_dat, file_index, to_u32(len)?)?;
+        Ok(layout
+            .iter()
+            .map(|(offset, size)| offset + size)
+            .max()
+            .unwrap_or(inner_dat.size)
+            .max(header_size(inner_dat)))
+    }
+
+    /// The aligned address just past the end of the DAT, where relocated InnerDATs are written
+    fn append_offset(&self) -> Result<u32, Error> {
+        let end = to_u32((&self.file).seek(SeekFrom::End(0))? as usize)?;
+        let alignment = infer_alignment(
+            self.inner_dats.iter().map(|i| i.offset),
+            MAX_ARCHIVE_ALIGNMENT,
+        );
+        Ok(align(end, alignment))
+    }
+
+    fn rebuild_inner(
+        &self,
+        inner_dat: &InnerDAT,
+        file_index: usize,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let layout = relocated_layout(inner_dat, file_index, to_u32(data.len())?)?;
+        let size = self.relocated_size(inner_dat, file_index, data.len())?;
+        let mut buffer = vec![0; size as usize];
+        // keep the original header, including any padding after the table
+        let header_len = header_size(inner_dat).min(first_offset(inner_dat)) as usize;
+        let mut io_file = &self.file;
+        io_file.seek(SeekFrom::Start(inner_dat.offset.into()))?;
+        io_file.read_exact(&mut buffer[..header_len])?;
+        for (i, (file, (offset, file_size))) in inner_dat.files.iter().zip(&layout).enumerate() {
+            let slot = &mut buffer[*offset as usize..(*offset + *file_size) as usize];
+            if i == file_index {
+                slot.copy_from_slice(data);
+            } else {
+                slot.copy_from_slice(&self.read_file(inner_dat, file)?);
+            }
+            let table = 4 + 8 * i;
+            buffer[table..table + 4].copy_from_slice(&offset.to_le_bytes());
+            buffer[table + 4..table + 8].copy_from_slice(&file_size.to_le_bytes());
+        }
+        Ok(buffer)
+    }
+
+    /// The 12 byte table entry describing an InnerDAT in the primary DAT
+    fn entry_bytes(&self, inner_index: usize) -> [u8; 12] {
+        let inner_dat = &self.inner_dats[inner_index];
+        let mut entry = [0; 12];
+        entry[0..4].copy_from_slice(&inner_dat.offset.to_le_bytes());
+        entry[4..8].copy_from_slice(&inner_dat.size.to_le_bytes());
+        entry[8..12].copy_from_slice(&inner_dat.entry_count.to_le_bytes());
+        entry
+    }
+
+    fn write_entry(&self, inner_index: usize, entry: &[u8; 12]) -> Result<(), Error> {
+        let mut io_file = &self.file;
+        io_file.seek(SeekFrom::Start(4 + 12 * inner_index as u64))?;
+        io_file.write_all(entry)
+    }
+}
+
+/// Lays out every file of an InnerDAT in its original order, with one file resized.
+/// Files keep their original alignment; only the files after the resized one move. Files that shared a payload keep
+/// sharing it, apart from the resized file, which is given a slot of its own.
+fn relocated_layout(
+    inner_dat: &InnerDAT,
+    file_index: usize,
+    len: u32,
+) -> Result<Vec<(u32, u32)>, Error> {
+    let alignment = infer_alignment(inner_dat.files.iter().map(|f| f.offset), MAX_FILE_ALIGNMENT);
+    let mut order: Vec<usize> = (0..inner_dat.files.len()).collect();
+    order.sort_by_key(|&i| inner_dat.files[i].offset);
+    let mut layout = vec![(0, 0); inner_dat.files.len()];
+    let mut cursor = first_offset(inner_dat);
+    // where each original payload was placed, by its original offset and size
+    let mut placed: HashMap<(u32, u32), u32> = HashMap::new();
+    for i in order {
+        let file = &inner_dat.files[i];
+        let size = if i == file_index { len } else { file.size };
+        if i != file_index && size > 0 {
+            if let Some(offset) = placed.get(&(file.offset, size)) {
+                layout[i] = (*offset, size);
+                continue;
+            }
+        }
+        let offset = align(cursor, alignment);
+        if i != file_index {
+            placed.insert((file.offset, size), offset);
+        }
+        layout[i] = (offset, size);
+        cursor = offset
+            .checked_add(size)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "rebuilt InnerDAT exceeds 4GiB"))?;
+    }
+    Ok(layout)
+}
+
+/// Whether another non-empty file points at the same payload as the file, as a session sharing duplicates writes
+fn is_shared(inner_dat: &InnerDAT, file_index: usize) -> bool {
+    let file = &inner_dat.files[file_index];
+    inner_dat
+        .files
+        .iter()
+        .enumerate()
+        .any(|(i, f)| i != file_index && f.offset == file.offset && f.size > 0)
+}
+
+/// The size of an InnerDAT's count and FileEntry table
+fn header_size(inner_dat: &InnerDAT) -> u32 {
+    4 + 8 * inner_dat.entry_count
+}
+
+/// The address of the first file in an InnerDAT, which marks the end of the padded header
+fn first_offset(inner_dat: &InnerDAT) -> u32 {
+    inner_dat
+        .files
+        .iter()
+        .map(|f| f.offset)
+        .min()
+        .unwrap_or_else(|| header_size(inner_dat))
+}
+
+/// Finds the largest power of two (up to `max`) that every offset is a multiple of
+pub(super) fn infer_alignment(offsets: impl Iterator<Item = u32>, max: u32) -> u32 {
+    let mut alignment = max;
+    for offset in offsets.filter(|&o| o != 0) {
+        while alignment > 1 && offset % alignment != 0 {
+            alignment /= 2;
+        }
+    }
+    alignment
+}
+
+pub(super) fn align(value: u32, alignment: u32) -> u32 {
+    value.div_ceil(alignment) * alignment
+}
+
+pub(super) fn to_u32(value: usize) -> Result<u32, Error> {
+    value
+        .try_into()
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "value does not fit in a DAT table"))
+}
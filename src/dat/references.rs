@@ -0,0 +1,327 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+};
+
+use super::{
+    tree::{dev_path, path_key},
+    InnerDAT, DAT,
+};
+use crate::{
+    errors::DecodeError,
+    file::{
+        put2d::Put2D,
+        renderware::{Dff, Txd},
+        FileType,
+    },
+};
+
+/// Every reference one file in a DAT makes to another, by path or by texture name
+#[derive(Debug, Default)]
+pub struct ReferenceGraph {
+    /// In table order of the referencing file
+    pub references: Vec<Reference>,
+    /// Files that make or hold references but can't be decoded, so whatever they reference is unknown
+    pub unreadable: Vec<Unreadable>,
+}
+
+#[derive(Debug)]
+pub struct Reference {
+    /// The InnerDAT and file index of the file making the reference
+    pub from: (usize, usize),
+    pub kind: ReferenceKind,
+    /// The path or texture name, as the referencing file gives it
+    pub target: String,
+    /// Every file the target could be; empty when the reference is dangling
+    pub resolved: Vec<(usize, usize)>,
+}
+
+#[derive(Debug)]
+pub struct Unreadable {
+    /// The InnerDAT and file index of the file
+    pub file: (usize, usize),
+    pub error: DecodeError,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// A Put2D script's `txd_path`
+    TextureDictionary,
+    /// A Put2D script's `txt_path`
+    Text,
+    /// A Put2D entry's `file_path`, which resolves to a file or a texture named after the image
+    Image,
+    /// A Put2D entry's `file_name` or a DFF material's texture
+    Texture,
+}
+
+impl Reference {
+    pub fn is_dangling(&self) -> bool {
+        self.resolved.is_empty()
+    }
+}
+
+impl ReferenceGraph {
+    /// The references a file makes
+    pub fn references_from(&self, archive: usize, file: usize) -> impl Iterator<Item = &Reference> {
+        self.references
+            .iter()
+            .filter(move |r| r.from == (archive, file))
+    }
+
+    /// The references that resolve to a file, such as every script and model using a texture dictionary
+    pub fn users_of(&self, archive: usize, file: usize) -> impl Iterator<Item = &Reference> {
+        self.references
+            .iter()
+            .filter(move |r| r.resolved.contains(&(archive, file)))
+    }
+
+    pub fn dangling(&self) -> impl Iterator<Item = &Reference> {
+        self.references.iter().filter(|r| r.is_dangling())
+    }
+
+    /// The graph in Graphviz DOT, with a node for every file that makes or resolves a reference and a red node for
+    /// every dangling target and unreadable file
+    pub fn to_dot(&self, dat: &DAT) -> String {
+        let mut dot = String::from("digraph references {\n    node [shape=box];\n");
+        let mut nodes: Vec<(usize, usize)> = self
+            .references
+            .iter()
+            .flat_map(|r| std::iter::once(r.from).chain(r.resolved.iter().copied()))
+            .collect();
+        nodes.sort();
+        nodes.dedup();
+        for (i, j) in nodes {
+            let _ = writeln!(
+                dot,
+                "    \"{i}-{j}\" [label=\"{}\"];",
+                escape(&file_label(dat, i, j))
+            );
+        }
+        for unreadable in &self.unreadable {
+            let (i, j) = unreadable.file;
+            let _ = writeln!(
+                dot,
+                "    \"{i}-{j}\" [label=\"{} (unreadable)\", color=red, fontcolor=red];",
+                escape(&file_label(dat, i, j))
+            );
+        }
+        for (k, reference) in self.references.iter().enumerate() {
+            let (i, j) = reference.from;
+            let label = escape(&format!("{} {}", reference.kind, reference.target));
+            if reference.is_dangling() {
+                let _ = writeln!(
+                    dot,
+                    "    \"dangling{k}\" [label=\"{}\", color=red, fontcolor=red];",
+                    escape(&reference.target)
+                );
+                let _ = writeln!(
+                    dot,
+                    "    \"{i}-{j}\" -> \"dangling{k}\" [label=\"{label}\", color=red];"
+                );
+            }
+            for (ti, tj) in &reference.resolved {
+                let _ = writeln!(dot, "    \"{i}-{j}\" -> \"{ti}-{tj}\" [label=\"{label}\"];");
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// What each file can be referred to by
+#[derive(Default)]
+struct Targets {
+    /// Lowercased name and extension to files, where the name is the file's name or else its archive's
+    files: HashMap<(String, String), Vec<(usize, usize)>>,
+    /// Lowercased texture name to the dictionaries holding it
+    textures: HashMap<String, Vec<(usize, usize)>>,
+}
+
+impl Targets {
+    fn by_path(&self, path: &str) -> Vec<(usize, usize)> {
+        path_key(path)
+            .and_then(|key| self.files.get(&key).cloned())
+            .unwrap_or_default()
+    }
+
+    fn by_texture(&self, name: &str) -> Vec<(usize, usize)> {
+        self.textures
+            .get(&name.to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+impl DAT {
+    /// Collects every reference in the DAT and resolves it against every file. Files that can't be read or decoded
+    /// are listed as unreadable.
+    pub fn references(&self) -> ReferenceGraph {
+        let mut unreadable = vec![];
+        let mut targets = Targets::default();
+        for (i, inner_dat) in self.inner_dats.iter().enumerate() {
+            for (j, file) in inner_dat.files.iter().enumerate() {
+                if let Some(name) = file.file_name.as_ref().or(inner_dat.archive_name.as_ref()) {
+                    targets
+                        .files
+                        .entry((name.to_lowercase(), file.file_type.to_string()))
+                        .or_default()
+                        .push((i, j));
+                }
+                if file.file_type == FileType::TXD {
+                    let textures = match file.decode::<Txd>(self, inner_dat) {
+                        Ok(txd) => txd.texture_names(),
+                        Err(error) => {
+                            unreadable.push(Unreadable {
+                                file: (i, j),
+                                error,
+                            });
+                            vec![]
+                        }
+                    };
+                    for texture in textures {
+                        targets
+                            .textures
+                            .entry(texture.to_lowercase())
+                            .or_default()
+                            .push((i, j));
+                    }
+                }
+            }
+        }
+
+        let mut references = vec![];
+        for (i, inner_dat) in self.inner_dats.iter().enumerate() {
+            for j in 0..inner_dat.files.len() {
+                let raw_references = match self.raw_references(inner_dat, j) {
+                    Ok(raw_references) => raw_references,
+                    Err(error) => {
+                        unreadable.push(Unreadable {
+                            file: (i, j),
+                            error,
+                        });
+                        continue;
+                    }
+                };
+                for (kind, target) in raw_references {
+                    let resolved = match kind {
+                        ReferenceKind::Texture => targets.by_texture(&target),
+                        ReferenceKind::Image => {
+                            let mut resolved = targets.by_path(&target);
+                            if let Some(stem) = dev_path(&target).and_then(|p| {
+                                p.file_stem().map(|s| s.to_string_lossy().into_owned())
+                            }) {
+                                resolved.extend(targets.by_texture(&stem));
+                            }
+                            resolved.sort_unstable();
+                            resolved.dedup();
+                            resolved
+                        }
+                        _ => targets.by_path(&target),
+                    };
+                    references.push(Reference {
+                        from: (i, j),
+                        kind,
+                        target,
+                        resolved,
+                    });
+                }
+            }
+        }
+        unreadable.sort_by_key(|u| u.file);
+        ReferenceGraph {
+            references,
+            unreadable,
+        }
+    }
+
+    /// The unresolved references a single file makes, skipping empty fields
+    fn raw_references(
+        &self,
+        inner_dat: &InnerDAT,
+        file: usize,
+    ) -> Result<Vec<(ReferenceKind, String)>, DecodeError> {
+        let file = &inner_dat.files[file];
+        let mut references = vec![];
+        match file.file_type {
+            FileType::PUT2D => {
+                let put2d_script = file.decode::<Put2D>(self, inner_dat)?;
+                references.push((
+                    ReferenceKind::TextureDictionary,
+                    put2d_script.txd_path.clone(),
+                ));
+                references.push((ReferenceKind::Text, put2d_script.txt_path.clone()));
+                for (path, name) in put2d_script.images() {
+                    references.push((ReferenceKind::Image, path.to_string()));
+                    references.push((ReferenceKind::Texture, name.to_string()));
+                }
+            }
+            FileType::DFF => {
+                let mut textures = file.decode::<Dff>(self, inner_dat)?.texture_names();
+                textures.sort();
+                textures.dedup();
+                references.extend(textures.into_iter().map(|t| (ReferenceKind::Texture, t)));
+            }
+            _ => {}
+        }
+        references.retain(|(_, target)| !target.trim().is_empty());
+        Ok(references)
+    }
+}
+
+/// The name `extract` would give a file
+fn file_label(dat: &DAT, archive: usize, file: usize) -> String {
+    let inner_dat = &dat.inner_dats[archive];
+    let f = &inner_dat.files[file];
+    format!(
+        "{}-{}{}",
+        inner_dat
+            .archive_name
+            .clone()
+            .unwrap_or(archive.to_string()),
+        f.file_name.clone().unwrap_or(file.to_string()),
+        f.file_type
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TextureDictionary => write!(f, "texture dictionary"),
+            Self::Text => write!(f, "text"),
+            Self::Image => write!(f, "image"),
+            Self::Texture => write!(f, "texture"),
+        }
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (i, j) = self.from;
+        write!(f, "{i}-{j} references {} {}", self.kind, self.target)?;
+        if self.is_dangling() {
+            return write!(f, ", which isn't in the DAT");
+        }
+        let resolved: Vec<String> = self
+            .resolved
+            .iter()
+            .map(|(i, j)| format!("{i}-{j}"))
+            .collect();
+        write!(f, " at {}", resolved.join(", "))
+    }
+}
+
+impl fmt::Display for Unreadable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (i, j) = self.file;
+        write!(
+            f,
+            "{i}-{j} can't be decoded, so its references are unknown: {}",
+            self.error
+        )
+    }
+}
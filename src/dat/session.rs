@@ -0,0 +1,378 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs::{self, File as ioFile, OpenOptions},
+    io::{BufWriter, Error, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::{
+    patch::{align, infer_alignment, to_u32, MAX_ARCHIVE_ALIGNMENT, MAX_FILE_ALIGNMENT},
+    DAT,
+};
+
+/// A file within an [`EditSession`], either still backed by the source DAT or staged in memory
+enum Slot {
+    /// The file at this index of the source InnerDAT
+    Original(usize),
+    /// Replacement or inserted contents
+    Staged(Vec<u8>),
+}
+
+/// Where an InnerDAT and its files end up in the written DAT
+struct ArchiveLayout {
+    offset: u32,
+    size: u32,
+    /// The address and size of each file, relative to the InnerDAT
+    files: Vec<(u32, u32)>,
+    /// Whether each file points at the payload of an earlier, identical file instead of its own copy
+    shared: Vec<bool>,
+}
+
+/// Stages replacements, insertions and deletions against a [`DAT`] without touching it.
+///
+/// Nothing is written until [`EditSession::commit`], which builds the new DAT in a temporary file,
+/// checks its tables, and then atomically renames it over the destination.
+pub struct EditSession<'a> {
+    dat: &'a DAT,
+    archives: Vec<Vec<Slot>>,
+    share_duplicates: bool,
+}
+
+impl<'a> EditSession<'a> {
+    pub fn new(dat: &'a DAT) -> Self {
+        let mut session = Self {
+            dat,
+            archives: vec![],
+            share_duplicates: false,
+        };
+        session.rollback();
+        session
+    }
+
+    /// Stages new contents for an existing file
+    pub fn replace(
+        &mut self,
+        inner_index: usize,
+        file_index: usize,
+        data: Vec<u8>,
+    ) -> Result<(), Error> {
+        let slot = self
+            .archive(inner_index)?
+            .get_mut(file_index)
+            .ok_or_else(|| missing_file(inner_index, file_index))?;
+        *slot = Slot::Staged(data);
+        Ok(())
+    }
+
+    /// Stages a new file, shifting the file at `file_index` and everything after it up by one
+    pub fn insert(
+        &mut self,
+        inner_index: usize,
+        file_index: usize,
+        data: Vec<u8>,
+    ) -> Result<(), Error> {
+        let archive = self.archive(inner_index)?;
+        if file_index > archive.len() {
+            return Err(missing_file(inner_index, file_index));
+        }
+        archive.insert(file_index, Slot::Staged(data));
+        Ok(())
+    }
+
+    /// Stages the removal of a file, shifting everything after it down by one
+    pub fn delete(&mut self, inner_index: usize, file_index: usize) -> Result<(), Error> {
+        let archive = self.archive(inner_index)?;
+        if file_index >= archive.len() {
+            return Err(missing_file(inner_index, file_index));
+        }
+        archive.remove(file_index);
+        Ok(())
+    }
+
+    /// Whether any changes are staged
+    pub fn is_dirty(&self) -> bool {
+        self.archives
+            .iter()
+            .zip(&self.dat.inner_dats)
+            .any(|(archive, inner_dat)| {
+                archive.len() != inner_dat.files.len()
+                    || archive
+                        .iter()
+                        .enumerate()
+                        .any(|(i, slot)| !matches!(slot, Slot::Original(j) if *j == i))
+            })
+    }
+
+    /// Discards every staged change
+    pub fn rollback(&mut self) {
+        self.archives = self
+            .dat
+            .inner_dats
+            .iter()
+            .map(|inner_dat| (0..inner_dat.files.len()).map(Slot::Original).collect())
+            .collect();
+    }
+
+    /// Writes identical files within an InnerDAT once, with every copy's FileEntry pointing at the same payload.
+    /// Copies in different InnerDATs are still written separately, even though a FileEntry could point forward into a
+    /// later InnerDAT, so only [`DuplicateGroup::recoverable`](super::duplicates::DuplicateGroup::recoverable) bytes
+    /// are saved.
+    ///
+    /// This is off by default, as it isn't known whether the game tolerates FileEntries that share data.
+    pub fn share_duplicates(&mut self, share: bool) {
+        self.share_duplicates = share;
+    }
+
+    /// Checks that the staged DAT can be laid out within the limits of the format
+    pub fn validate(&self) -> Result<(), Error> {
+        self.layout().map(|_| ())
+    }
+
+    /// Writes the staged DAT to `path`, which may be the file the source DAT was opened from.
+    ///
+    /// The DAT is written to a temporary file beside `path` and only renamed into place once its tables have been
+    /// read back and checked, so `path` is never left half-written.
+    pub fn commit(self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let layout = self.layout()?;
+        let temp_path = temp_path(path)?;
+        let result = self
+            .write(&temp_path, &layout)
+            .and_then(|_| verify(&temp_path, &layout))
+            .and_then(|_| fs::rename(&temp_path, path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    fn archive(&mut self, inner_index: usize) -> Result<&mut Vec<Slot>, Error> {
+        self.archives.get_mut(inner_index).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("InnerDAT {inner_index} does not exist"),
+            )
+        })
+    }
+
+    fn slot_size(&self, inner_index: usize, slot: &Slot) -> Result<u32, Error> {
+        match slot {
+            Slot::Original(i) => Ok(self.dat.inner_dats[inner_index].files[*i].size),
+            Slot::Staged(data) => to_u32(data.len()),
+        }
+    }
+
+    fn slot_data<'s>(&'s self, inner_index: usize, slot: &'s Slot) -> Result<Cow<'s, [u8]>, Error> {
+        match slot {
+            Slot::Original(i) => {
+                let inner_dat = &self.dat.inner_dats[inner_index];
+                Ok(Cow::Owned(
+                    self.dat.read_file(inner_dat, &inner_dat.files[*i])?,
+                ))
+            }
+            Slot::Staged(data) => Ok(Cow::Borrowed(data)),
+        }
+    }
+
+    fn layout(&self) -> Result<Vec<ArchiveLayout>, Error> {
+        let too_large = || Error::new(ErrorKind::InvalidData, "staged DAT exceeds 4GiB");
+        let archive_alignment = infer_alignment(
+            self.dat.inner_dats.iter().map(|i| i.offset),
+            MAX_ARCHIVE_ALIGNMENT,
+        );
+        let mut cursor = 4 + 12 * to_u32(self.archives.len())?;
+        let mut layout = vec![];
+        for (i, archive) in self.archives.iter().enumerate() {
+            let file_alignment = infer_alignment(
+                self.dat.inner_dats[i].files.iter().map(|f| f.offset),
+                MAX_FILE_ALIGNMENT,
+            );
+            let offset = align(cursor, archive_alignment);
+            let mut files = vec![];
+            let mut shared = vec![];
+            // the offset of each distinct payload written so far, by size and CRC32
+            let mut payloads: HashMap<(u32, u32), Vec<(usize, u32)>> = HashMap::new();
+            let mut file_cursor = 4 + 8 * to_u32(archive.len())?;
+            for (j, slot) in archive.iter().enumerate() {
+                let size = self.slot_size(i, slot)?;
+                if self.share_duplicates && size > 0 {
+                    let data = self.slot_data(i, slot)?;
+                    let copies = payloads.entry((size, crc32fast::hash(&data))).or_default();
+                    let mut existing = None;
+                    for (k, offset) in copies.iter() {
+                        if self.slot_data(i, &archive[*k])? == data {
+                            existing = Some(*offset);
+                            break;
+                        }
+                    }
+                    if let Some(offset) = existing {
+                        files.push((offset, size));
+                        shared.push(true);
+                        continue;
+                    }
+                    copies.push((j, align(file_cursor, file_alignment)));
+                }
+                let file_offset = align(file_cursor, file_alignment);
+                files.push((file_offset, size));
+                shared.push(false);
+                file_cursor = file_offset.checked_add(size).ok_or_else(too_large)?;
+            }
+            cursor = offset.checked_add(file_cursor).ok_or_else(too_large)?;
+            layout.push(ArchiveLayout {
+                offset,
+                size: file_cursor,
+                files,
+                shared,
+            });
+        }
+        Ok(layout)
+    }
+
+    fn write(&self, path: &Path, layout: &[ArchiveLayout]) -> Result<(), Error> {
+        let io_file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        let mut writer = PositionWriter {
+            inner: BufWriter::new(&io_file),
+            position: 0,
+        };
+        writer.write_u32(to_u32(layout.len())?)?;
+        for (archive, archive_layout) in self.archives.iter().zip(layout) {
+            writer.write_u32(archive_layout.offset)?;
+            writer.write_u32(archive_layout.size)?;
+            writer.write_u32(to_u32(archive.len())?)?;
+        }
+        for (i, (archive, archive_layout)) in self.archives.iter().zip(layout).enumerate() {
+            let start = u64::from(archive_layout.offset);
+            writer.pad_to(start)?;
+            writer.write_u32(to_u32(archive.len())?)?;
+            for (offset, size) in &archive_layout.files {
+                writer.write_u32(*offset)?;
+                writer.write_u32(*size)?;
+            }
+            let files = archive_layout.files.iter().zip(&archive_layout.shared);
+            for (slot, ((offset, _), shared)) in archive.iter().zip(files) {
+                if *shared {
+                    continue;
+                }
+                writer.pad_to(start + u64::from(*offset))?;
+                writer.write_all(&self.slot_data(i, slot)?)?;
+            }
+        }
+        writer.inner.flush()?;
+        drop(writer);
+        io_file.sync_all()
+    }
+}
+
+/// Keeps track of how far into the output it has written, so gaps can be zero padded
+struct PositionWriter<W: Write> {
+    inner: W,
+    position: u64,
+}
+
+impl<W: Write> PositionWriter<W> {
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.inner.write_all(bytes)?;
+        self.position += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn write_u32(&mut self, value: u32) -> Result<(), Error> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn pad_to(&mut self, position: u64) -> Result<(), Error> {
+        let padding = vec![0; (position - self.position) as usize];
+        self.write_all(&padding)
+    }
+}
+
+impl DAT {
+    /// Starts an [`EditSession`] for staging changes to this DAT
+    pub fn edit(&self) -> EditSession<'_> {
+        EditSession::new(self)
+    }
+}
+
+/// Reads the tables of a freshly written DAT back and checks them against the layout it was written from
+fn verify(path: &Path, layout: &[ArchiveLayout]) -> Result<(), Error> {
+    let mut io_file = ioFile::open(path)?;
+    let length = io_file.metadata()?.len();
+    let mismatch = |what: String| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{what} does not match after writing"),
+        )
+    };
+    let read_u32 = |io_file: &mut ioFile| -> Result<u32, Error> {
+        let mut buf: [u8; 4] = [0; 4];
+        io_file.read_exact(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    };
+
+    if read_u32(&mut io_file)? as usize != layout.len() {
+        return Err(mismatch("InnerDAT count".to_string()));
+    }
+    for (i, archive) in layout.iter().enumerate() {
+        io_file.seek(SeekFrom::Start(4 + 12 * i as u64))?;
+        let entry = [
+            read_u32(&mut io_file)?,
+            read_u32(&mut io_file)?,
+            read_u32(&mut io_file)?,
+        ];
+        if entry != [archive.offset, archive.size, to_u32(archive.files.len())?]
+            || u64::from(archive.offset) + u64::from(archive.size) > length
+        {
+            return Err(mismatch(format!("table entry {i}")));
+        }
+        io_file.seek(SeekFrom::Start(archive.offset.into()))?;
+        if read_u32(&mut io_file)? as usize != archive.files.len() {
+            return Err(mismatch(format!("file count of InnerDAT {i}")));
+        }
+        for (j, file) in archive.files.iter().enumerate() {
+            if (read_u32(&mut io_file)?, read_u32(&mut io_file)?) != *file {
+                return Err(mismatch(format!("file entry {i}-{j}")));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A new hidden file beside `path`, so the final rename never crosses filesystems. The name is unique to this process
+/// and commit, and an existing file is never reused, so concurrent commits and files left by a crash can't collide.
+fn temp_path(path: &Path) -> Result<PathBuf, Error> {
+    static COMMITS: AtomicUsize = AtomicUsize::new(0);
+    let name = path
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "destination is not a file"))?;
+    loop {
+        let commit = COMMITS.fetch_add(1, Ordering::Relaxed);
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}-{commit}.tmp",
+            name.to_string_lossy(),
+            process::id()
+        ));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(_) => return Ok(temp_path),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn missing_file(inner_index: usize, file_index: usize) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("file {inner_index}-{file_index} does not exist"),
+    )
+}
@@ -0,0 +1,178 @@
+use std::{
+    fmt,
+    io::{Error, Read, Seek, SeekFrom},
+    ops::Range,
+};
+
+use super::DAT;
+use crate::file::{detect::Detection, FileType};
+
+/// What a claimed byte range of the DAT is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Claim {
+    /// The entry count and Entry table at the start of the DAT
+    Table,
+    /// An InnerDAT's file count and FileEntry table
+    InnerHeader {
+        inner: usize,
+    },
+    File {
+        inner: usize,
+        file: usize,
+    },
+}
+
+/// An unclaimed range of the DAT that holds something other than padding
+#[derive(Debug)]
+pub struct SlackRegion {
+    /// Absolute offsets within the DAT, with leading and trailing padding trimmed off
+    pub range: Range<u64>,
+    /// The type the detectors give the region's contents, as though it were a file
+    pub detection: Detection,
+}
+
+impl SlackRegion {
+    pub fn file_type(&self) -> Option<&FileType> {
+        self.detection
+            .chosen
+            .as_ref()
+            .map(|c| &c.detected.file_type)
+    }
+}
+
+/// Every byte range of a DAT, split into what's claimed by a table entry and what isn't
+#[derive(Debug)]
+pub struct SlackReport {
+    pub dat_size: u64,
+    /// Sorted by start offset. Ranges may overlap if entries share data.
+    pub claimed: Vec<(Range<u64>, Claim)>,
+    /// Unclaimed bytes that are all zeros
+    pub padding: u64,
+    pub slack: Vec<SlackRegion>,
+}
+
+impl SlackReport {
+    /// The total size of the unclaimed regions that aren't padding
+    pub fn slack_size(&self) -> u64 {
+        self.slack.iter().map(|r| r.range.end - r.range.start).sum()
+    }
+}
+
+impl DAT {
+    /// Maps every byte of the DAT to the table entry that claims it, and runs detection on whatever's left over
+    pub fn scan_slack(&self) -> Result<SlackReport, Error> {
+        let dat_size = self.file.metadata()?.len();
+        let mut claimed = vec![(0..4 + 12 * self.inner_dats.len() as u64, Claim::Table)];
+        for (i, inner_dat) in self.inner_dats.iter().enumerate() {
+            let offset = u64::from(inner_dat.offset);
+            claimed.push((
+                offset..offset + 4 + 8 * u64::from(inner_dat.entry_count),
+                Claim::InnerHeader { inner: i },
+            ));
+            for (j, file) in inner_dat.files.iter().enumerate() {
+                let start = offset + u64::from(file.offset);
+                claimed.push((
+                    start..start + u64::from(file.size),
+                    Claim::File { inner: i, file: j },
+                ));
+            }
+        }
+        claimed.sort_by_key(|(range, _)| (range.start, range.end));
+
+        let mut gaps = vec![];
+        let mut covered = 0;
+        for (range, _) in &claimed {
+            if range.start > covered {
+                gaps.push(covered..range.start.min(dat_size));
+            }
+            covered = covered.max(range.end);
+        }
+        if covered < dat_size {
+            gaps.push(covered..dat_size);
+        }
+
+        let mut padding = 0;
+        let mut slack = vec![];
+        for gap in gaps.into_iter().filter(|g| !g.is_empty()) {
+            let data = self.read_range(gap.clone())?;
+            let Some(first) = data.iter().position(|b| *b != 0) else {
+                padding += data.len() as u64;
+                continue;
+            };
+            let last = data.iter().rposition(|b| *b != 0).unwrap();
+            padding += (data.len() - (last + 1 - first)) as u64;
+            slack.push(SlackRegion {
+                range: gap.start + first as u64..gap.start + last as u64 + 1,
+                detection: self.detectors.explain(&data[first..=last]),
+            });
+        }
+        Ok(SlackReport {
+            dat_size,
+            claimed,
+            padding,
+            slack,
+        })
+    }
+
+    /// Reads the contents of a slack region, so it can be carved out as a file
+    pub fn read_slack(&self, region: &SlackRegion) -> Result<Vec<u8>, Error> {
+        self.read_range(region.range.clone())
+    }
+
+    fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>, Error> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(range.start))?;
+        let mut buffer = vec![0; (range.end - range.start) as usize];
+        file.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+impl fmt::Display for Claim {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Table => write!(f, "entry table"),
+            Self::InnerHeader { inner } => write!(f, "InnerDAT {inner} header"),
+            Self::File { inner, file } => write!(f, "file {inner}-{file}"),
+        }
+    }
+}
+
+impl fmt::Display for SlackRegion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:#x}..{:#x} ({} bytes): ",
+            self.range.start,
+            self.range.end,
+            self.range.end - self.range.start
+        )?;
+        match &self.detection.chosen {
+            Some(chosen) => write!(
+                f,
+                "{} ({:.0}% confident)",
+                chosen.detector,
+                chosen.detected.confidence * 100.0
+            ),
+            None => write!(f, "unknown data"),
+        }
+    }
+}
+
+impl fmt::Display for SlackReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let claimed: u64 = self.dat_size - self.padding - self.slack_size();
+        writeln!(
+            f,
+            "{} bytes: {claimed} claimed, {} padding, {} in {} unclaimed regions",
+            self.dat_size,
+            self.padding,
+            self.slack_size(),
+            self.slack.len()
+        )?;
+        for region in &self.slack {
+            writeln!(f, "{region}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+use super::{InnerDAT, DAT};
+use crate::{
+    file::{mif::MifScript, name::Name, put2d::Put2D},
+    modpack::{FileReplacement, FileTarget, ModPackage},
+};
+
+/// Where every file in a DAT sat in the original development tree, as far as the paths embedded in its scripts tell.
+///
+/// Files the scripts don't name are placed beside a file from the same archive that is known, and anything else is
+/// laid out by index as `<archive>/<file>`.
+#[derive(Debug)]
+pub struct DevTree {
+    /// Every file in the DAT, in table order
+    pub files: Vec<TreeFile>,
+}
+
+#[derive(Debug)]
+pub struct TreeFile {
+    pub archive: usize,
+    pub file: usize,
+    /// Relative to the root of the tree
+    pub path: PathBuf,
+    pub origin: PathOrigin,
+}
+
+/// How a file's place in the tree was decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathOrigin {
+    /// A script in the same archive gives the file's dev path
+    Embedded,
+    /// Placed in the directory of a known file from the same archive, under the name the toolbox gives it
+    Sibling,
+    /// Nothing is known about the file, so it's placed by index
+    Index,
+}
+
+impl DevTree {
+    pub fn count(&self, origin: PathOrigin) -> usize {
+        self.files.iter().filter(|f| f.origin == origin).count()
+    }
+
+    /// Writes every file to its place under `root`, along with a `mod.json` mapping each path back to its file so
+    /// the tree can be repacked with [`modpack::merge`](crate::modpack::merge)
+    pub fn extract(&self, dat: &DAT, root: impl AsRef<Path>) -> Result<(), Error> {
+        let root = root.as_ref();
+        for tree_file in &self.files {
+            let inner_dat = &dat.inner_dats[tree_file.archive];
+            let path = root.join(&tree_file.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(
+                path,
+                dat.read_file(inner_dat, &inner_dat.files[tree_file.file])?,
+            )?;
+        }
+        self.to_mod_package(dat).save(root)
+    }
+
+    /// A mod package replacing every file with its copy in the tree
+    pub fn to_mod_package(&self, dat: &DAT) -> ModPackage {
+        let files = self
+            .files
+            .iter()
+            .map(|tree_file| FileReplacement {
+                target: FileTarget {
+                    archive: tree_file.archive,
+                    archive_name: None,
+                    file: tree_file.file,
+                    file_name: None,
+                    file_type: Some(
+                        dat.inner_dats[tree_file.archive].files[tree_file.file]
+                            .file_type
+                            .to_string(),
+                    ),
+                },
+                path: tree_file.path.clone(),
+            })
+            .collect();
+        ModPackage {
+            name: "Development tree".to_string(),
+            version: None,
+            files,
+            stats: vec![],
+            root: PathBuf::new(),
+        }
+    }
+}
+
+impl InnerDAT {
+    /// The dev paths embedded in the archive's Put2D, NAME and MIF scripts, in file order, with the `.out` suffix
+    /// of build outputs removed
+    pub fn dev_paths(&self, dat: &DAT) -> Vec<PathBuf> {
+        let mut paths = vec![];
+        for file in &self.files {
+            if let Ok(put2d_script) = file.decode::<Put2D>(dat, self) {
+                paths.push(put2d_script.txd_path.clone());
+                paths.push(put2d_script.txt_path.clone());
+                paths.extend(
+                    put2d_script
+                        .images()
+                        .into_iter()
+                        .map(|(path, _)| path.to_string()),
+                );
+            } else if let Ok(name) = file.decode::<Name>(dat, self) {
+                paths.push(name.file_path);
+            } else if let Ok(script) = file.decode::<MifScript>(dat, self) {
+                paths.extend(script.paths().into_iter().map(str::to_string));
+            }
+        }
+        paths.iter().filter_map(|path| dev_path(path)).collect()
+    }
+}
+
+impl DAT {
+    /// Places every file in the development tree. Paths that would collide, such as the same texture dictionary
+    /// referenced from several archives, are only given to the first file claiming them.
+    pub fn dev_tree(&self) -> DevTree {
+        let mut files = vec![];
+        // lowercased, since the paths came from Windows
+        let mut taken: HashSet<String> = HashSet::new();
+        let mut claim = |path: &Path| taken.insert(path.to_string_lossy().to_lowercase());
+        for (i, inner_dat) in self.inner_dats.iter().enumerate() {
+            let dev_paths = inner_dat.dev_paths(self);
+            let mut paths: Vec<Option<PathBuf>> = vec![None; inner_dat.files.len()];
+            for dev_path in &dev_paths {
+                let Some(extension) = dev_path.extension() else {
+                    continue;
+                };
+                let extension = format!(".{}", extension.to_string_lossy().to_lowercase());
+                // only trust a path when there's no doubt about which file it names
+                let mut candidates = inner_dat
+                    .files
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| f.file_type.to_string() == extension);
+                if let (Some((j, _)), None) = (candidates.next(), candidates.next()) {
+                    if paths[j].is_none() {
+                        paths[j] = Some(dev_path.clone());
+                    }
+                }
+            }
+            let directory = paths
+                .iter()
+                .flatten()
+                .chain(&dev_paths)
+                .next()
+                .and_then(|path| path.parent())
+                .map(Path::to_path_buf);
+
+            for (j, file) in inner_dat.files.iter().enumerate() {
+                let mut placed = paths[j].take().map(|path| (path, PathOrigin::Embedded));
+                if placed.is_none() {
+                    placed = directory.as_ref().map(|directory| {
+                        let file_name =
+                            format!("{}{}", sibling_name(inner_dat, i, j), file.file_type);
+                        (directory.join(file_name), PathOrigin::Sibling)
+                    });
+                }
+                let (path, origin) = match placed {
+                    Some((path, origin)) if claim(&path) => (path, origin),
+                    _ => {
+                        let path = Path::new(&i.to_string()).join(format!("{j}{}", file.file_type));
+                        claim(&path);
+                        (path, PathOrigin::Index)
+                    }
+                };
+                files.push(TreeFile {
+                    archive: i,
+                    file: j,
+                    path,
+                    origin,
+                });
+            }
+        }
+        DevTree { files }
+    }
+}
+
+/// Turns an embedded Windows path into one relative to the root of the tree, dropping the drive and anything that
+/// would escape the root
+pub(super) fn dev_path(path: &str) -> Option<PathBuf> {
+    let path = path.trim();
+    let path = path.strip_suffix(".out").unwrap_or(path);
+    let relative: PathBuf = path
+        .split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != "." && *c != ".." && !c.ends_with(':'))
+        .collect();
+    (relative.components().count() > 0).then_some(relative)
+}
+
+/// The lowercased name and extension of the file an embedded path refers to, such as `("title", ".txd")`
+pub(super) fn path_key(path: &str) -> Option<(String, String)> {
+    let path = dev_path(path)?;
+    Some((
+        path.file_stem()?.to_string_lossy().to_lowercase(),
+        format!(".{}", path.extension()?.to_string_lossy().to_lowercase()),
+    ))
+}
+
+/// The name `extract` gives a file, for files whose own dev path isn't known
+fn sibling_name(inner_dat: &InnerDAT, archive: usize, file: usize) -> String {
+    let archive_name = inner_dat
+        .archive_name
+        .clone()
+        .unwrap_or(archive.to_string());
+    match &inner_dat.files[file].file_name {
+        Some(file_name) if *file_name == archive_name => archive_name,
+        Some(file_name) => format!("{archive_name}-{file_name}"),
+        None => format!("{archive_name}-{file}"),
+    }
+}
+
+impl fmt::Display for TreeFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{} {}", self.archive, self.file, self.path.display())
+    }
+}
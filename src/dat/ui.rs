@@ -0,0 +1,245 @@
+use std::{cmp::Reverse, fmt};
+
+use super::{
+    tree::{dev_path, path_key},
+    ArchiveType, InnerDAT, DAT,
+};
+use crate::{
+    errors::{ArchiveError, DecodeError},
+    file::{detect, put2d::Put2D, renderware::Txd, txd_name, txt::Txt, File, FileType},
+};
+
+/// A [`ArchiveType::UI`] archive: a Put2D script, followed by the texture dictionary and text resource it references
+pub struct UiArchive<'a> {
+    inner_dat: &'a InnerDAT,
+    put2d_script: Put2D,
+}
+
+/// A resource the Put2D script references that isn't in the archive
+#[derive(Debug, PartialEq)]
+pub enum MissingResource {
+    Texture(String),
+    Text(String),
+    Image(String),
+}
+
+impl<'a> UiArchive<'a> {
+    pub fn new(dat: &DAT, inner_dat: &'a InnerDAT) -> Result<Self, ArchiveError> {
+        if inner_dat.archive_type != ArchiveType::UI {
+            return Err(ArchiveError::WrongType(inner_dat.archive_type));
+        }
+        let put2d_file = inner_dat
+            .files
+            .first()
+            .ok_or_else(|| ArchiveError::MissingSlot("put2d".to_string()))?;
+        Ok(Self {
+            inner_dat,
+            put2d_script: put2d_file.decode(dat, inner_dat)?,
+        })
+    }
+
+    pub fn put2d_file(&self) -> &'a File {
+        &self.inner_dat.files[0]
+    }
+
+    pub fn put2d_script(&self) -> &Put2D {
+        &self.put2d_script
+    }
+
+    /// The name of the texture dictionary, which is also the archive's name
+    pub fn name(&self) -> Option<String> {
+        txd_name(&self.put2d_script)
+    }
+
+    /// The texture dictionary referenced by `txd_path`
+    pub fn texture(&self) -> Option<&'a File> {
+        self.resolve(&self.put2d_script.txd_path)
+    }
+
+    /// The text resource referenced by `txt_path`
+    pub fn text_file(&self) -> Option<&'a File> {
+        self.resolve(&self.put2d_script.txt_path)
+    }
+
+    /// Reads and parses the text resource, if the archive has one
+    pub fn text(&self, dat: &DAT) -> Option<Result<Txt, DecodeError>> {
+        self.text_file()
+            .map(|file| file.decode(dat, self.inner_dat))
+    }
+
+    /// The file after the script that a path in it refers to, by the file's name and type
+    pub fn resolve(&self, path: &str) -> Option<&'a File> {
+        let (name, extension) = path_key(path)?;
+        self.inner_dat.files.iter().skip(1).find(|f| {
+            f.file_type.to_string() == extension
+                && f.file_name
+                    .as_ref()
+                    .is_some_and(|n| n.to_lowercase() == name)
+        })
+    }
+
+    /// Every path the Put2D script references that resolves to nothing: the texture dictionary, the text resource,
+    /// and images that are neither a file in the archive nor a texture in the dictionary
+    pub fn missing(&self, dat: &DAT) -> Vec<MissingResource> {
+        let put2d_script = &self.put2d_script;
+        let mut missing = vec![];
+        if !put2d_script.txd_path.trim().is_empty() && self.texture().is_none() {
+            missing.push(MissingResource::Texture(put2d_script.txd_path.clone()));
+        }
+        if !put2d_script.txt_path.trim().is_empty() && self.text_file().is_none() {
+            missing.push(MissingResource::Text(put2d_script.txt_path.clone()));
+        }
+        let textures = self
+            .texture()
+            .map(|file| textures(dat, self.inner_dat, file))
+            .unwrap_or_default();
+        for (path, name) in put2d_script.images() {
+            let found = self.resolve(path).is_some()
+                || image_names(path, name).any(|n| textures.contains(&n));
+            let image = MissingResource::Image(path.to_string());
+            if !found && !missing.contains(&image) {
+                missing.push(image);
+            }
+        }
+        missing
+    }
+
+    /// Names the script after the archive, and the texture dictionary and text resource it links to after the paths
+    /// that reference them. Unrecognized files share the archive's name.
+    pub(super) fn file_names(&self, dat: &DAT) -> Vec<(usize, String)> {
+        let mut names = vec![];
+        if let Some(name) = self.name() {
+            names.push((0, name.clone()));
+            names.extend(
+                self.inner_dat
+                    .files
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| f.file_type == FileType::UNKNOWN)
+                    .map(|(i, _)| (i, name.clone())),
+            );
+        }
+        if let (Some(i), Some(name)) = (
+            self.link_texture(dat),
+            file_stem(&self.put2d_script.txd_path),
+        ) {
+            names.push((i, name));
+        }
+        if let (Some(i), Some(name)) = (self.link_text(), file_stem(&self.put2d_script.txt_path)) {
+            names.push((i, name));
+        }
+        names
+    }
+
+    /// The texture dictionary holding the most textures the script's images use, or the first one if it uses none.
+    /// Dictionaries don't know their own name, so this is what decides which one `txd_path` refers to.
+    fn link_texture(&self, dat: &DAT) -> Option<usize> {
+        if self.put2d_script.txd_path.trim().is_empty() {
+            return None;
+        }
+        let mut dictionaries = self
+            .inner_dat
+            .files
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, f)| f.file_type == FileType::TXD);
+        let used: Vec<String> = self
+            .put2d_script
+            .images()
+            .into_iter()
+            .flat_map(|(path, name)| image_names(path, name))
+            .collect();
+        if used.is_empty() {
+            return dictionaries.next().map(|(i, _)| i);
+        }
+        dictionaries
+            .map(|(i, file)| {
+                let textures = textures(dat, self.inner_dat, file);
+                (used.iter().filter(|n| textures.contains(n)).count(), i)
+            })
+            .filter(|(count, _)| *count > 0)
+            .max_by_key(|(count, i)| (*count, Reverse(*i)))
+            .map(|(_, i)| i)
+    }
+
+    /// The text resource `txt_path` refers to. Text resources don't know their own name either, and a script only
+    /// ever references one, so it's the first.
+    fn link_text(&self) -> Option<usize> {
+        if self.put2d_script.txt_path.trim().is_empty() {
+            return None;
+        }
+        self.inner_dat
+            .files
+            .iter()
+            .skip(1)
+            .position(|f| f.file_type == FileType::TXT)
+            .map(|i| i + 1)
+    }
+}
+
+/// The name of the file a path refers to, without its extension
+fn file_stem(path: &str) -> Option<String> {
+    Some(dev_path(path)?.file_stem()?.to_string_lossy().into_owned())
+}
+
+/// The lowercased names an image could have in a texture dictionary: its texture name, or its path's name
+fn image_names<'s>(path: &'s str, name: &'s str) -> impl Iterator<Item = String> + 's {
+    std::iter::once(name.trim().to_lowercase())
+        .chain(path_key(path).map(|(stem, _)| stem))
+        .filter(|n| !n.is_empty())
+}
+
+/// The lowercased names of the textures in a dictionary
+fn textures(dat: &DAT, inner_dat: &InnerDAT, file: &File) -> Vec<String> {
+    file.decode::<Txd>(dat, inner_dat)
+        .map(|txd| {
+            txd.texture_names()
+                .iter()
+                .map(|t| t.to_lowercase())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl fmt::Display for MissingResource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Texture(path) => write!(f, "texture dictionary {path} is not in the archive"),
+            Self::Text(path) => write!(f, "text resource {path} is not in the archive"),
+            Self::Image(path) => write!(
+                f,
+                "image {path} is not in the archive or its texture dictionary"
+            ),
+        }
+    }
+}
+
+impl InnerDAT {
+    /// Unrecognized files after a UI archive's script that are plain text. Text resources don't always start with a
+    /// header, so on their own they're left unknown.
+    pub(super) fn text_resources(&self, dat: &DAT) -> Vec<usize> {
+        self.files
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, f)| f.file_type == FileType::UNKNOWN)
+            .filter(|(_, f)| {
+                f.read_file(dat, self)
+                    .is_ok_and(|data| detect::plain_text(&data).is_some())
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+impl DAT {
+    /// A typed view of the archive at `inner_index`, if it's a UI archive
+    pub fn ui(&self, inner_index: usize) -> Result<UiArchive<'_>, ArchiveError> {
+        let inner_dat = self
+            .inner_dats
+            .get(inner_index)
+            .ok_or(ArchiveError::MissingArchive(inner_index))?;
+        UiArchive::new(self, inner_dat)
+    }
+}
@@ -0,0 +1,393 @@
+//! Binary patches between two versions of a file, such as an original and a modded DATA.DAT or a full disc image.
+//!
+//! A patch only stores the bytes that can't be copied from the original, so mods can be shared without
+//! distributing the game's data. Both [`diff`] and [`apply`] stream their inputs and never hold a whole file in memory.
+//!
+//! # Format
+//! All data is in little endian.
+//!
+//! | Type | Variable | Description |
+//! | ---- | -------- | ----------- |
+//! | u8[8] | Magic | `BTDELTA1` |
+//! | u64 | SourceSize | The size of the original file. |
+//! | u32 | SourceCRC | The CRC32 of the original file. |
+//! | Op[] | Ops | Instructions that rebuild the modified file, terminated by an End op. |
+//! | u64 | TargetSize | The size of the modified file. |
+//! | u32 | TargetCRC | The CRC32 of the modified file. |
+//!
+//! Each Op starts with a u8 tag: `0` copies a u64 length of bytes from a u64 offset in the original,
+//! `1` is followed by a u64 length and that many literal bytes, and `0xFF` ends the list.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{BufReader, BufWriter, Error, ErrorKind, Read, Seek, SeekFrom, Write},
+};
+
+use crc32fast::Hasher;
+use sha1_smol::Sha1;
+
+const MAGIC: &[u8; 8] = b"BTDELTA1";
+/// The granularity copies are found at; one CD sector, which is what DAT contents are aligned to
+const BLOCK_SIZE: usize = 0x800;
+/// How many source blocks are remembered for any one weak hash, so runs of padding don't blow up the index
+const MAX_CANDIDATES: usize = 8;
+/// Literal runs are split once they reach this size, to keep memory use bounded
+const MAX_LITERAL: usize = 0x10_0000;
+
+/// Every whole block of the source, for finding the blocks the target copies
+struct SourceIndex {
+    /// Maps the weak hash of a block to the offsets in the source it was seen at
+    weak: HashMap<u32, Vec<u64>>,
+    /// The SHA-1 of each block by block number, which a weak hash match is confirmed against
+    strong: Vec<[u8; 20]>,
+    size: u64,
+    crc32: u32,
+}
+
+impl SourceIndex {
+    /// Whether the block at `offset`, which is always a multiple of the block size, has the SHA-1 `digest`
+    fn matches(&self, offset: u64, digest: &[u8; 20]) -> bool {
+        self.strong
+            .get((offset / BLOCK_SIZE as u64) as usize)
+            .is_some_and(|strong| strong == digest)
+    }
+}
+
+const OP_COPY: u8 = 0;
+const OP_LITERAL: u8 = 1;
+const OP_END: u8 = 0xFF;
+
+/// Statistics about a generated patch
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DeltaSummary {
+    pub source_size: u64,
+    pub target_size: u64,
+    /// Bytes of the modified file that are copied from the original
+    pub copied: u64,
+    /// Bytes of the modified file that are stored in the patch
+    pub literal: u64,
+}
+
+/// Writes a patch to `patch` that turns `source` into `target`
+pub fn diff<S, T, P>(source: &mut S, target: &mut T, patch: &mut P) -> Result<DeltaSummary, Error>
+where
+    S: Read + Seek,
+    T: Read,
+    P: Write,
+{
+    let index = index_source(source)?;
+    let mut patch = BufWriter::new(patch);
+    patch.write_all(MAGIC)?;
+    patch.write_all(&index.size.to_le_bytes())?;
+    patch.write_all(&index.crc32.to_le_bytes())?;
+
+    let mut summary = DeltaSummary {
+        source_size: index.size,
+        ..Default::default()
+    };
+    let mut target_hasher = Hasher::new();
+    let mut target = BufReader::new(target).bytes();
+    let mut next_byte = || -> Result<Option<u8>, Error> {
+        match target.next().transpose()? {
+            Some(byte) => {
+                target_hasher.update(&[byte]);
+                Ok(Some(byte))
+            }
+            None => Ok(None),
+        }
+    };
+
+    let mut ops = OpWriter {
+        patch: &mut patch,
+        copy: None,
+        literal: vec![],
+        summary: &mut summary,
+    };
+    let mut window: VecDeque<u8> = VecDeque::with_capacity(BLOCK_SIZE);
+    let mut expected: Option<u64> = None;
+    'fill: loop {
+        while window.len() < BLOCK_SIZE {
+            match next_byte()? {
+                Some(byte) => window.push_back(byte),
+                None => break,
+            }
+        }
+        let mut hash = RollingHash::new(window.make_contiguous());
+        while !window.is_empty() {
+            if window.len() == BLOCK_SIZE {
+                let mut candidates = expected
+                    .iter()
+                    .chain(index.weak.get(&hash.value()).into_iter().flatten())
+                    .peekable();
+                // only hashed once there's a candidate, since most windows have none
+                let digest = match candidates.peek() {
+                    Some(_) => Sha1::from(window.make_contiguous()).digest().bytes(),
+                    None => [0; 20],
+                };
+                for &offset in candidates {
+                    if index.matches(offset, &digest) {
+                        ops.copy(offset, BLOCK_SIZE as u64)?;
+                        expected = Some(offset + BLOCK_SIZE as u64);
+                        window.clear();
+                        continue 'fill;
+                    }
+                }
+            }
+            expected = None;
+            let old = window.pop_front().unwrap();
+            ops.literal(old)?;
+            // once the target runs out the window can never fill again, so the hash no longer matters
+            if let Some(new) = next_byte()? {
+                window.push_back(new);
+                hash.roll(old, new);
+            }
+        }
+        break;
+    }
+    ops.finish()?;
+
+    summary.target_size = summary.copied + summary.literal;
+    patch.write_all(&summary.target_size.to_le_bytes())?;
+    patch.write_all(&target_hasher.finalize().to_le_bytes())?;
+    patch.flush()?;
+    Ok(summary)
+}
+
+/// Rebuilds the modified file described by `patch` from `source`, writing it to `target`.
+///
+/// Fails without writing anything if `source` isn't the file the patch was made from, and fails after writing
+/// if the result doesn't match the checksum recorded in the patch.
+pub fn apply<S, P, T>(source: &mut S, patch: &mut P, target: &mut T) -> Result<(), Error>
+where
+    S: Read + Seek,
+    P: Read,
+    T: Write,
+{
+    let mut patch = BufReader::new(patch);
+    let mut magic = [0; 8];
+    patch.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a patch file"));
+    }
+    let source_size = read_u64(&mut patch)?;
+    let source_crc = read_u32(&mut patch)?;
+    let (actual_size, actual_crc) = checksum(source)?;
+    if (actual_size, actual_crc) != (source_size, source_crc) {
+        return Err(invalid(&format!(
+            "source does not match the patch (expected {source_size} bytes with CRC32 {source_crc:08X}, found {actual_size} bytes with CRC32 {actual_crc:08X})"
+        )));
+    }
+
+    let mut target = BufWriter::new(target);
+    let mut hasher = Hasher::new();
+    let mut written: u64 = 0;
+    let mut buffer = vec![0; MAX_LITERAL];
+    loop {
+        let mut tag = [0];
+        patch.read_exact(&mut tag)?;
+        let (mut remaining, from_source) = match tag[0] {
+            OP_COPY => {
+                let offset = read_u64(&mut patch)?;
+                let len = read_u64(&mut patch)?;
+                match offset.checked_add(len) {
+                    Some(end) if end <= source_size => {}
+                    _ => return Err(invalid("copy extends past the end of the source")),
+                }
+                source.seek(SeekFrom::Start(offset))?;
+                (len, true)
+            }
+            OP_LITERAL => (read_u64(&mut patch)?, false),
+            OP_END => break,
+            _ => return Err(invalid("unknown patch instruction")),
+        };
+        while remaining > 0 {
+            let chunk = &mut buffer[..remaining.min(MAX_LITERAL as u64) as usize];
+            if from_source {
+                source.read_exact(chunk)?;
+            } else {
+                patch.read_exact(chunk)?;
+            }
+            hasher.update(chunk);
+            target.write_all(chunk)?;
+            remaining -= chunk.len() as u64;
+            written += chunk.len() as u64;
+        }
+    }
+    let target_size = read_u64(&mut patch)?;
+    let target_crc = read_u32(&mut patch)?;
+    target.flush()?;
+    let crc = hasher.finalize();
+    if (written, crc) != (target_size, target_crc) {
+        return Err(invalid(&format!(
+            "patched file does not match (expected {target_size} bytes with CRC32 {target_crc:08X}, produced {written} bytes with CRC32 {crc:08X})"
+        )));
+    }
+    Ok(())
+}
+
+/// Computes the size and CRC32 of a whole stream, leaving it at the end
+pub fn checksum<R: Read + Seek>(reader: &mut R) -> Result<(u64, u32), Error> {
+    reader.seek(SeekFrom::Start(0))?;
+    let mut hasher = Hasher::new();
+    let mut buffer = vec![0; MAX_LITERAL];
+    let mut size: u64 = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((size, hasher.finalize()))
+}
+
+/// Hashes every whole block of the source, returning the index along with the source's size and CRC32
+fn index_source<R: Read + Seek>(source: &mut R) -> Result<SourceIndex, Error> {
+    source.seek(SeekFrom::Start(0))?;
+    let mut reader = BufReader::new(&mut *source);
+    let mut weak: HashMap<u32, Vec<u64>> = HashMap::new();
+    let mut strong = vec![];
+    let mut hasher = Hasher::new();
+    let mut block = vec![0; BLOCK_SIZE];
+    let mut offset: u64 = 0;
+    loop {
+        let mut filled = 0;
+        while filled < BLOCK_SIZE {
+            let read = reader.read(&mut block[filled..])?;
+            if read == 0 {
+                break;
+            }
+            filled += read;
+        }
+        hasher.update(&block[..filled]);
+        if filled < BLOCK_SIZE {
+            offset += filled as u64;
+            break;
+        }
+        let candidates = weak.entry(RollingHash::new(&block).value()).or_default();
+        if candidates.len() < MAX_CANDIDATES {
+            candidates.push(offset);
+        }
+        strong.push(Sha1::from(&block).digest().bytes());
+        offset += BLOCK_SIZE as u64;
+    }
+    Ok(SourceIndex {
+        weak,
+        strong,
+        size: offset,
+        crc32: hasher.finalize(),
+    })
+}
+
+/// Buffers ops so adjacent copies are merged and literals are written in runs
+struct OpWriter<'a, W: Write> {
+    patch: &'a mut W,
+    /// The pending copy's source offset and length
+    copy: Option<(u64, u64)>,
+    literal: Vec<u8>,
+    summary: &'a mut DeltaSummary,
+}
+
+impl<W: Write> OpWriter<'_, W> {
+    fn copy(&mut self, offset: u64, len: u64) -> Result<(), Error> {
+        self.flush_literal()?;
+        self.copy = match self.copy {
+            Some((start, pending)) if start + pending == offset => Some((start, pending + len)),
+            _ => {
+                self.flush_copy()?;
+                Some((offset, len))
+            }
+        };
+        self.summary.copied += len;
+        Ok(())
+    }
+
+    fn literal(&mut self, byte: u8) -> Result<(), Error> {
+        self.flush_copy()?;
+        self.literal.push(byte);
+        self.summary.literal += 1;
+        if self.literal.len() >= MAX_LITERAL {
+            self.flush_literal()?;
+        }
+        Ok(())
+    }
+
+    fn flush_copy(&mut self) -> Result<(), Error> {
+        if let Some((offset, len)) = self.copy.take() {
+            self.patch.write_all(&[OP_COPY])?;
+            self.patch.write_all(&offset.to_le_bytes())?;
+            self.patch.write_all(&len.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn flush_literal(&mut self) -> Result<(), Error> {
+        if !self.literal.is_empty() {
+            self.patch.write_all(&[OP_LITERAL])?;
+            self.patch
+                .write_all(&(self.literal.len() as u64).to_le_bytes())?;
+            self.patch.write_all(&self.literal)?;
+            self.literal.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.flush_copy()?;
+        self.flush_literal()?;
+        self.patch.write_all(&[OP_END])
+    }
+}
+
+/// An Adler-style checksum that can slide along a stream one byte at a time
+struct RollingHash {
+    a: u32,
+    b: u32,
+    len: u32,
+}
+
+impl RollingHash {
+    fn new(data: &[u8]) -> Self {
+        let mut hash = Self {
+            a: 0,
+            b: 0,
+            len: data.len() as u32,
+        };
+        for (i, &byte) in data.iter().enumerate() {
+            hash.a = hash.a.wrapping_add(byte.into());
+            hash.b = hash
+                .b
+                .wrapping_add((data.len() as u32 - i as u32).wrapping_mul(byte.into()));
+        }
+        hash
+    }
+
+    fn roll(&mut self, old: u8, new: u8) {
+        self.a = self.a.wrapping_sub(old.into()).wrapping_add(new.into());
+        self.b = self
+            .b
+            .wrapping_sub(self.len.wrapping_mul(old.into()))
+            .wrapping_add(self.a);
+    }
+
+    fn value(&self) -> u32 {
+        (self.a & 0xFFFF) | (self.b << 16)
+    }
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, Error> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, Error> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
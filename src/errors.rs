@@ -10,4 +10,98 @@ pub enum ValidationError {
     OutOfRange(String, u8),
     #[error("unknown parsing error")]
     ParseFailure,
+    #[error("`{0}` can't be encoded as Shift-JIS")]
+    Unencodable(String),
+    /// Lines are numbered from 1 and fields, the space-separated tokens of a line, from 0
+    #[error("line {line}, field {field}: expected {expected}, found `{token}`")]
+    InvalidToken {
+        line: usize,
+        field: usize,
+        expected: &'static str,
+        token: String,
+    },
+    #[error("line {line}, field {field}: expected {expected}, found nothing")]
+    MissingToken {
+        line: usize,
+        field: usize,
+        expected: &'static str,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum ModError {
+    #[error("mod `{0}` has an invalid manifest: {1}")]
+    InvalidManifest(String, String),
+    #[error("mod `{0}` targets {1}, which does not match the DAT")]
+    TargetMismatch(String, String),
+    #[error("mod `{0}` could not edit {1}: {2}")]
+    InvalidEdit(String, String, ValidationError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum DecodeError {
+    #[error("a {0} file can't be decoded as the requested format")]
+    WrongType(String),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum ConvertError {
+    #[error("don't know how to convert `{0}` to `{1}`")]
+    Unsupported(String, String),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+    #[error(transparent)]
+    Schema(#[from] SchemaError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum ManifestError {
+    #[error("`{0}` is not a valid hash manifest: {1}")]
+    Invalid(String, String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum SchemaError {
+    #[error("`{0}` is not a valid field schema: {1}")]
+    Invalid(String, String),
+    #[error("Put2D type {0} has no field named `{1}`")]
+    UnknownField(String, String),
+    #[error("Put2D type {0} is missing field `{1}`")]
+    MissingField(String, String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum RulesError {
+    #[error("`{0}` has invalid archive rules: {1}")]
+    Invalid(String, String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("InnerDAT {0} does not exist")]
+    MissingArchive(usize),
+    #[error("archive is a {0:?} archive, not the requested kind")]
+    WrongType(crate::dat::ArchiveType),
+    #[error("slot `{0}` is missing or holds the wrong type of file")]
+    MissingSlot(String),
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
 }
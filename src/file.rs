@@ -1,10 +1,23 @@
+pub mod decode;
+pub mod detect;
+pub mod fti;
+pub mod mapinfo;
+pub mod mif;
 pub mod name;
+pub mod png;
 pub mod put2d;
+pub mod renderware;
+pub mod schema;
 pub mod stats;
+pub mod txt;
 
 use std::{fmt, io::Error};
 
-use crate::dat::{InnerDAT, DAT};
+use crate::{
+    dat::{InnerDAT, DAT},
+    errors::DecodeError,
+};
+use decode::{Decode, Decoded};
 
 pub struct File {
     pub file_type: FileType,
@@ -14,76 +27,57 @@ pub struct File {
 }
 
 impl File {
-    pub fn new(
-        dat_file: &DAT,
-        inner_dat: &InnerDAT,
-        entry: [u8; 8],
-        current_files: &[File],
-    ) -> Self {
+    pub fn new(dat_file: &DAT, inner_dat: &InnerDAT, entry: [u8; 8]) -> Self {
         let mut file = Self {
             file_type: FileType::UNKNOWN,
+            // files are named once the whole archive is indexed and classified
             file_name: None,
             offset: u32::from_le_bytes(entry[0..4].try_into().expect("invalid table entry offset")),
             size: u32::from_le_bytes(entry[4..8].try_into().expect("invalid table entry size")),
         };
         let data = dat_file.read_file(inner_dat, &file).unwrap();
-        file.file_type = FileType::from_data(&data);
-        file.file_name = match &file.file_type {
-            FileType::PUT2D { put2d_script } => Some(
-                put2d_script
-                    .txd_path
-                    .clone()
-                    .split('/')
-                    .last()
-                    .unwrap()
-                    .strip_suffix(".txd")
-                    .unwrap()
-                    .to_string(),
-            ),
-            FileType::ANM => {
-                if current_files.len() > 7 {
-                    if let FileType::NAME { name } = &current_files.get(6).unwrap().file_type {
-                        Some(name.names.get(current_files.len() - 7).unwrap().clone())
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            }
-            FileType::TXD | FileType::UNKNOWN => {
-                if !current_files.is_empty() {
-                    if let FileType::PUT2D { put2d_script } =
-                        &current_files.first().unwrap().file_type
-                    {
-                        Some(
-                            put2d_script
-                                .txd_path
-                                .clone()
-                                .split('/')
-                                .last()
-                                .unwrap()
-                                .strip_suffix(".txd")
-                                .unwrap()
-                                .to_string(),
-                        )
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            }
-            _ => None,
-        };
+        file.file_type = dat_file.detectors().detect(&data);
         file
     }
 
     pub fn read_file(&self, dat_file: &DAT, inner_dat: &InnerDAT) -> Result<Vec<u8>, Error> {
         dat_file.read_file(inner_dat, self)
     }
+
+    /// Reads and parses the file as `T`, which must be the format the file was detected as
+    pub fn decode<T: Decode>(
+        &self,
+        dat_file: &DAT,
+        inner_dat: &InnerDAT,
+    ) -> Result<T, DecodeError> {
+        if !T::accepts(&self.file_type) {
+            return Err(DecodeError::WrongType(self.file_type.to_string()));
+        }
+        Ok(T::decode(&self.read_file(dat_file, inner_dat)?)?)
+    }
+
+    /// Reads and parses the file into whichever format it was detected as
+    pub fn decode_any(&self, dat_file: &DAT, inner_dat: &InnerDAT) -> Result<Decoded, DecodeError> {
+        Ok(Decoded::decode(
+            &self.file_type,
+            &self.read_file(dat_file, inner_dat)?,
+        )?)
+    }
 }
 
+/// The name of the texture dictionary a Put2D script uses, which is also the name of its archive
+pub(crate) fn txd_name(put2d_script: &put2d::Put2D) -> Option<String> {
+    let file_name = put2d_script.txd_path.split('/').next_back()?;
+    Some(
+        file_name
+            .strip_suffix(".txd")
+            .unwrap_or(file_name)
+            .to_string(),
+    )
+}
+
+/// The format a file was detected as. Use [`File::decode`] to parse a file's contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FileType {
     /// RenderWare Anim Animation File (0x1B)
     ANM,
@@ -102,152 +96,29 @@ pub enum FileType {
     /// Baskelian Map Info File
     MAPINFO,
     /// Baskelian Name Data Script
-    NAME {
-        name: name::Name,
-    },
+    NAME,
     /// Portable Network Graphics File
     PNG,
     /// Baskelian MIF PostBL Data Script
     POSTBL,
     /// Baskelian Put2D Script
-    PUT2D {
-        put2d_script: put2d::Put2D,
-    },
+    PUT2D,
     /// Baskelian Stats File
-    STATS {
-        stats_file: stats::Stats,
-    },
+    STATS,
     /// RenderWare Texture Dictionary (0x16)
     TXD,
+    /// Shift-JIS Text Resource
     TXT,
+    /// A format recognized by a detector registered outside the toolbox
+    OTHER {
+        /// The name of the detector that recognized the file
+        format: String,
+        /// The extension given to extracted files, including the leading dot
+        extension: String,
+    },
     UNKNOWN,
 }
 
-impl FileType {
-    fn from_data(data: &[u8]) -> Self {
-        if data.is_empty() {
-            Self::UNKNOWN
-        } else {
-            // TODO: Make this code cleaner
-            match data[0] {
-                0x10 => Self::DFF,
-                0x16 => Self::TXD,
-                0x1B => Self::ANM,
-                0x1E => Self::DMA,
-                _ => {
-                    // check for PNG signature
-                    if data.len() >= 4 && data[1..4] == [0x50, 0x4E, 0x47] {
-                        return Self::PNG;
-                    }
-                    // put2d signature
-                    if data.len() > 12
-                        && data[0..12]
-                            == [
-                                0x70, 0x75, 0x74, 0x32, 0x64, 0x2D, 0x73, 0x63, 0x72, 0x69, 0x70,
-                                0x74,
-                            ]
-                    {
-                        return Self::PUT2D {
-                            put2d_script: put2d::Put2D::from_data(data).unwrap(),
-                        };
-                    }
-                    // mif fixed signature
-                    if data.len() > 21
-                        && data[0..21]
-                            == [
-                                0x6D, 0x69, 0x66, 0x2D, 0x66, 0x69, 0x78, 0x65, 0x64, 0x2D, 0x64,
-                                0x61, 0x74, 0x61, 0x2D, 0x73, 0x63, 0x72, 0x69, 0x70, 0x74,
-                            ]
-                    {
-                        return Self::FIXED;
-                    }
-                    // mif attached signature
-                    if data.len() > 24
-                        && data[0..24]
-                            == [
-                                0x6D, 0x69, 0x66, 0x2D, 0x61, 0x74, 0x74, 0x61, 0x63, 0x68, 0x65,
-                                0x64, 0x2D, 0x64, 0x61, 0x74, 0x61, 0x2D, 0x73, 0x63, 0x72, 0x69,
-                                0x70, 0x74,
-                            ]
-                    {
-                        return Self::ATTACHED;
-                    }
-                    // mif postbl signature
-                    if data.len() > 22
-                        && data[0..22]
-                            == [
-                                0x6D, 0x69, 0x66, 0x2D, 0x70, 0x6F, 0x73, 0x74, 0x62, 0x6C, 0x2D,
-                                0x64, 0x61, 0x74, 0x61, 0x2D, 0x73, 0x63, 0x72, 0x69, 0x70, 0x74,
-                            ]
-                    {
-                        return Self::POSTBL;
-                    }
-                    // mif comid signature
-                    if data.len() > 21
-                        && data[0..21]
-                            == [
-                                0x6D, 0x69, 0x66, 0x2D, 0x63, 0x6F, 0x6D, 0x69, 0x64, 0x2D, 0x64,
-                                0x61, 0x74, 0x61, 0x2D, 0x73, 0x63, 0x72, 0x69, 0x70, 0x74,
-                            ]
-                    {
-                        return Self::COMID;
-                    }
-                    // mif name signature
-                    if data.len() > 20
-                        && data[0..20]
-                            == [
-                                0x6D, 0x69, 0x66, 0x2D, 0x6E, 0x61, 0x6D, 0x65, 0x2D, 0x64, 0x61,
-                                0x74, 0x61, 0x2D, 0x73, 0x63, 0x72, 0x69, 0x70, 0x74,
-                            ]
-                    {
-                        return Self::NAME {
-                            name: name::Name::new(data),
-                        };
-                    }
-                    // check for "font-type" signature
-                    if data.len() >= 20
-                        && data[0..9] == [0x66, 0x6F, 0x6E, 0x74, 0x2D, 0x74, 0x79, 0x70, 0x65]
-                    {
-                        return Self::FTI;
-                    }
-                    let mut current_index: usize = 0;
-                    let mut space_count: u8 = 0;
-                    while current_index < 150
-                        && current_index < data.len()
-                        && data[current_index] != 0x0A
-                    {
-                        if data[current_index] == 0x20 {
-                            space_count += 1;
-                        }
-                        current_index += 1;
-                    }
-                    if space_count == 20 {
-                        Self::STATS {
-                            stats_file: stats::Stats::from_data(data),
-                        }
-                    } else {
-                        let mut current_index: usize = 12;
-                        let mut buffer_count: u8 = 0;
-                        while current_index < 32
-                            && current_index < data.len()
-                            && data[current_index] == 0xFF
-                        {
-                            buffer_count += 1;
-                            current_index += 1;
-                        }
-                        if buffer_count == 12 && data.len() > 29 && data[8] == data[28] {
-                            // data [8] and data[28] are expected to be the Map ID value of the map that the MAPINFO file is referencing
-                            Self::MAPINFO
-                        } else {
-                            Self::UNKNOWN
-                        }
-                    }
-                }
-            }
-        }
-    }
-}
-
 impl fmt::Display for FileType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -259,13 +130,14 @@ impl fmt::Display for FileType {
             Self::FIXED => write!(f, ".fix"),
             Self::FTI => write!(f, ".fti"),
             Self::MAPINFO => write!(f, ".mapinfo"),
-            Self::NAME { .. } => write!(f, ".name"),
+            Self::NAME => write!(f, ".name"),
             Self::PNG => write!(f, ".png"),
             Self::POSTBL => write!(f, ".postbl"),
-            Self::PUT2D { .. } => write!(f, ".put2d"),
-            Self::STATS { .. } => write!(f, ".stats"),
+            Self::PUT2D => write!(f, ".put2d"),
+            Self::STATS => write!(f, ".stats"),
             Self::TXD => write!(f, ".txd"),
             Self::TXT => write!(f, ".txt"),
+            Self::OTHER { extension, .. } => write!(f, "{extension}"),
             Self::UNKNOWN => write!(f, ""),
         }
     }
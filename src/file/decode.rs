@@ -0,0 +1,68 @@
+use super::{
+    fti::Fti,
+    mapinfo::MapInfo,
+    mif::MifScript,
+    name::Name,
+    png::Png,
+    put2d::Put2D,
+    renderware::{Anm, Dff, Dma, Txd},
+    stats::Stats,
+    txt::Txt,
+    FileType,
+};
+use crate::errors::ValidationError;
+
+/// A file format that can be parsed from a file's contents
+pub trait Decode: Sized {
+    /// Whether files detected as `file_type` are in this format
+    fn accepts(file_type: &FileType) -> bool;
+    fn decode(data: &[u8]) -> Result<Self, ValidationError>;
+}
+
+/// A file parsed into the format matching its [`FileType`]
+#[derive(Debug)]
+pub enum Decoded {
+    ANM(Anm),
+    ATTACHED(MifScript),
+    COMID(MifScript),
+    DMA(Dma),
+    DFF(Dff),
+    FIXED(MifScript),
+    FTI(Fti),
+    MAPINFO(MapInfo),
+    NAME(Name),
+    PNG(Png),
+    POSTBL(MifScript),
+    PUT2D(Put2D),
+    STATS(Stats),
+    TXD(Txd),
+    TXT(Txt),
+    /// Formats without a parser keep their raw contents
+    OTHER(Vec<u8>),
+    UNKNOWN(Vec<u8>),
+}
+
+impl Decoded {
+    /// Parses data according to its detected type
+    pub fn decode(file_type: &FileType, data: &[u8]) -> Result<Self, ValidationError> {
+        Ok(match file_type {
+            FileType::ANM => Self::ANM(Anm::decode(data)?),
+            FileType::ATTACHED => Self::ATTACHED(MifScript::decode(data)?),
+            FileType::COMID => Self::COMID(MifScript::decode(data)?),
+            FileType::DMA => Self::DMA(Dma::decode(data)?),
+            FileType::DFF => Self::DFF(Dff::decode(data)?),
+            FileType::FIXED => Self::FIXED(MifScript::decode(data)?),
+            FileType::FTI => Self::FTI(Fti::decode(data)?),
+            FileType::MAPINFO => Self::MAPINFO(MapInfo::decode(data)?),
+            FileType::NAME => Self::NAME(Name::decode(data)?),
+            FileType::PNG => Self::PNG(Png::decode(data)?),
+            FileType::POSTBL => Self::POSTBL(MifScript::decode(data)?),
+            FileType::PUT2D => Self::PUT2D(Put2D::decode(data)?),
+            FileType::STATS => Self::STATS(Stats::decode(data)?),
+            FileType::TXD => Self::TXD(Txd::decode(data)?),
+            FileType::TXT => Self::TXT(Txt::decode(data)?),
+            FileType::OTHER { .. } => Self::OTHER(data.to_vec()),
+            FileType::UNKNOWN => Self::UNKNOWN(data.to_vec()),
+        })
+    }
+}
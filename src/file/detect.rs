@@ -0,0 +1,491 @@
+use std::fmt;
+
+use encoding_rs::SHIFT_JIS;
+
+use super::{txt, FileType};
+
+/// A detector recognizing a file, along with how sure it is and why
+#[derive(Debug)]
+pub struct Match {
+    pub file_type: FileType,
+    /// From 0 (a guess) to 1 (certain)
+    pub confidence: f32,
+    /// A description of the rule that matched
+    pub rule: String,
+}
+
+/// A detector that recognized a file, as reported by [`DetectorRegistry::explain`]
+#[derive(Debug)]
+pub struct Candidate {
+    pub detector: String,
+    pub priority: i32,
+    pub detected: Match,
+}
+
+/// Why a file was given its type
+#[derive(Debug)]
+pub struct Detection {
+    /// The candidate that decided the file's type, or `None` if the file is [`FileType::UNKNOWN`]
+    pub chosen: Option<Candidate>,
+    /// Other detectors that also recognized the file, in the order they were tried
+    pub runners_up: Vec<Candidate>,
+    /// Detectors that weren't tried because the file is shorter than their minimum length
+    pub too_short: Vec<String>,
+}
+
+/// Recognizes a file format from a file's contents
+pub trait FormatDetector: Send + Sync {
+    /// A short, human-readable name for the format
+    fn name(&self) -> &str;
+    /// Detectors with a higher priority are tried first
+    fn priority(&self) -> i32 {
+        0
+    }
+    /// Files shorter than this are never passed to [`FormatDetector::detect`]
+    fn min_len(&self) -> usize {
+        1
+    }
+    /// Returns the file's type if the data is in this detector's format
+    fn detect(&self, data: &[u8]) -> Option<Match>;
+}
+
+/// Detects a format by a fixed signature at a given offset
+pub struct MagicDetector {
+    pub name: String,
+    pub offset: usize,
+    pub magic: Vec<u8>,
+    pub min_len: usize,
+    pub priority: i32,
+    /// How sure a matching signature makes the detector
+    pub confidence: f32,
+    /// Builds the file type once the signature matches
+    pub file_type: fn(&[u8]) -> FileType,
+}
+
+impl FormatDetector for MagicDetector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    fn min_len(&self) -> usize {
+        self.min_len.max(self.offset + self.magic.len())
+    }
+
+    fn detect(&self, data: &[u8]) -> Option<Match> {
+        if data[self.offset..self.offset + self.magic.len()] == self.magic[..] {
+            Some(Match {
+                file_type: (self.file_type)(data),
+                confidence: self.confidence,
+                rule: format!(
+                    "signature {} at offset {}",
+                    describe_bytes(&self.magic),
+                    self.offset
+                ),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Detects a format with an arbitrary check over the data
+pub struct HeuristicDetector {
+    pub name: String,
+    pub min_len: usize,
+    pub priority: i32,
+    pub detect: fn(&[u8]) -> Option<Match>,
+}
+
+impl FormatDetector for HeuristicDetector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    fn min_len(&self) -> usize {
+        self.min_len
+    }
+
+    fn detect(&self, data: &[u8]) -> Option<Match> {
+        (self.detect)(data)
+    }
+}
+
+/// An ordered set of [`FormatDetector`]s used to classify files while indexing a DAT
+pub struct DetectorRegistry {
+    detectors: Vec<Box<dyn FormatDetector>>,
+}
+
+impl Default for DetectorRegistry {
+    /// A registry with every format the toolbox knows about
+    fn default() -> Self {
+        let mut registry = Self::new();
+        for detector in builtin_detectors() {
+            registry.register(detector);
+        }
+        registry
+    }
+}
+
+impl DetectorRegistry {
+    /// An empty registry, which classifies everything as [`FileType::UNKNOWN`]
+    pub fn new() -> Self {
+        Self { detectors: vec![] }
+    }
+
+    /// Adds a detector. Detectors with equal priority are tried in the order they were registered.
+    pub fn register(&mut self, detector: Box<dyn FormatDetector>) {
+        let index = self
+            .detectors
+            .partition_point(|d| d.priority() >= detector.priority());
+        self.detectors.insert(index, detector);
+    }
+
+    /// The registered detectors, in the order they're tried
+    pub fn detectors(&self) -> impl Iterator<Item = &dyn FormatDetector> {
+        self.detectors.iter().map(|d| d.as_ref())
+    }
+
+    /// Classifies a file using the first detector that recognizes it
+    pub fn detect(&self, data: &[u8]) -> FileType {
+        self.detectors
+            .iter()
+            .filter(|d| data.len() >= d.min_len())
+            .find_map(|d| d.detect(data))
+            .map_or(FileType::UNKNOWN, |m| m.file_type)
+    }
+
+    /// Runs every detector over a file, reporting which one decided its type and which others also matched
+    pub fn explain(&self, data: &[u8]) -> Detection {
+        let mut detection = Detection {
+            chosen: None,
+            runners_up: vec![],
+            too_short: vec![],
+        };
+        for detector in &self.detectors {
+            if data.len() < detector.min_len() {
+                detection.too_short.push(detector.name().to_string());
+                continue;
+            }
+            if let Some(detected) = detector.detect(data) {
+                let candidate = Candidate {
+                    detector: detector.name().to_string(),
+                    priority: detector.priority(),
+                    detected,
+                };
+                if detection.chosen.is_none() {
+                    detection.chosen = Some(candidate);
+                } else {
+                    detection.runners_up.push(candidate);
+                }
+            }
+        }
+        detection
+    }
+}
+
+fn builtin_detectors() -> Vec<Box<dyn FormatDetector>> {
+    // each detector has its own priority so the original detection order is kept
+    let renderware = |name: &str, priority: i32, detect: fn(&[u8]) -> Option<Match>| {
+        Box::new(HeuristicDetector {
+            name: name.to_string(),
+            min_len: 1,
+            priority,
+            detect,
+        }) as Box<dyn FormatDetector>
+    };
+    let script = |name: &str,
+                  magic: &str,
+                  min_len: usize,
+                  priority: i32,
+                  file_type: fn(&[u8]) -> FileType| {
+        Box::new(MagicDetector {
+            name: name.to_string(),
+            offset: 0,
+            magic: magic.as_bytes().to_vec(),
+            min_len,
+            priority,
+            confidence: 1.0,
+            file_type,
+        }) as Box<dyn FormatDetector>
+    };
+    vec![
+        renderware("RenderWare Clump", 130, |data| {
+            detect_renderware(data, 0x10, FileType::DFF)
+        }),
+        renderware("RenderWare Texture Dictionary", 120, |data| {
+            detect_renderware(data, 0x16, FileType::TXD)
+        }),
+        renderware("RenderWare Anim Animation", 110, |data| {
+            detect_renderware(data, 0x1B, FileType::ANM)
+        }),
+        renderware("RenderWare Delta Morph Animation", 100, |data| {
+            detect_renderware(data, 0x1E, FileType::DMA)
+        }),
+        Box::new(HeuristicDetector {
+            name: "PNG".to_string(),
+            min_len: 4,
+            priority: 90,
+            detect: detect_png,
+        }),
+        script("Put2D Script", "put2d-script", 13, 80, |_| FileType::PUT2D),
+        script(
+            "MIF Fixed Data Script",
+            "mif-fixed-data-script",
+            22,
+            70,
+            |_| FileType::FIXED,
+        ),
+        script(
+            "MIF Attached Data Script",
+            "mif-attached-data-script",
+            25,
+            60,
+            |_| FileType::ATTACHED,
+        ),
+        script(
+            "MIF PostBL Data Script",
+            "mif-postbl-data-script",
+            23,
+            50,
+            |_| FileType::POSTBL,
+        ),
+        script(
+            "MIF Comid Data Script",
+            "mif-comid-data-script",
+            22,
+            40,
+            |_| FileType::COMID,
+        ),
+        script("Name Data Script", "mif-name-data-script", 21, 30, |_| {
+            FileType::NAME
+        }),
+        script("Font Type Information", "font-type", 20, 20, |_| {
+            FileType::FTI
+        }),
+        Box::new(HeuristicDetector {
+            name: "Stats".to_string(),
+            min_len: 1,
+            priority: 10,
+            detect: detect_stats,
+        }),
+        Box::new(HeuristicDetector {
+            name: "Map Info".to_string(),
+            min_len: 30,
+            priority: 0,
+            detect: detect_mapinfo,
+        }),
+        // text is the least specific format, so it's only considered once everything else has been ruled out
+        Box::new(HeuristicDetector {
+            name: "Text".to_string(),
+            min_len: 1,
+            priority: -10,
+            detect: detect_txt,
+        }),
+    ]
+}
+
+/// RenderWare files start with a chunk header: a u32 chunk ID, the u32 size of the chunk's data, and a u32 library
+/// version stamp. Only the first byte decides the type, the rest of the header only affects the confidence.
+fn detect_renderware(data: &[u8], id: u8, file_type: FileType) -> Option<Match> {
+    if data[0] != id {
+        return None;
+    }
+    let mut rule = format!("first byte is RenderWare chunk ID {id:#04X}");
+    let mut confidence = 0.3;
+    if data.len() >= 12 {
+        let word = |i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+        if word(0) == u32::from(id) {
+            confidence += 0.2;
+            rule.push_str(", full chunk ID matches");
+        }
+        if word(4) as usize <= data.len() - 12 {
+            confidence += 0.2;
+            rule.push_str(", chunk size fits in the file");
+        }
+        if is_renderware_version(word(8)) {
+            confidence += 0.3;
+            rule.push_str(", plausible library version stamp");
+        }
+    }
+    Some(Match {
+        file_type,
+        confidence,
+        rule,
+    })
+}
+
+/// Whether a library stamp decodes to a RenderWare 3.x version
+pub(crate) fn is_renderware_version(stamp: u32) -> bool {
+    // stamps from 3.1 onward pack the version into the high bits, older files store it plainly
+    let version = if stamp & 0xFFFF0000 != 0 {
+        (((stamp >> 14) & 0x3FF00) + 0x30000) | ((stamp >> 16) & 0x3F)
+    } else {
+        stamp << 8
+    };
+    (0x30000..0x38000).contains(&version)
+}
+
+/// PNG files start with 0x89 followed by `PNG`
+fn detect_png(data: &[u8]) -> Option<Match> {
+    if data[1..4] != *b"PNG" {
+        return None;
+    }
+    let signature = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+    Some(if data.starts_with(&signature) {
+        Match {
+            file_type: FileType::PNG,
+            confidence: 1.0,
+            rule: "full 8 byte PNG signature".to_string(),
+        }
+    } else {
+        Match {
+            file_type: FileType::PNG,
+            confidence: 0.6,
+            rule: "bytes 1 to 3 are `PNG`".to_string(),
+        }
+    })
+}
+
+/// Stats files have exactly 20 spaces before the first newline
+fn detect_stats(data: &[u8]) -> Option<Match> {
+    let spaces = |line: &[u8]| line.iter().take(150).filter(|b| **b == 0x20).count();
+    let mut lines = data.split(|b| *b == 0x0A);
+    if spaces(lines.next().unwrap()) != 20 {
+        return None;
+    }
+    // every entry in a real stats file has the same layout, so the more lines agree the surer the match
+    let others: Vec<usize> = lines.filter(|l| !l.is_empty()).map(spaces).collect();
+    let agreeing = others.iter().filter(|s| **s == 20).count();
+    let (confidence, rule) = if others.is_empty() {
+        (
+            0.4,
+            "exactly 20 spaces before the first newline".to_string(),
+        )
+    } else {
+        (
+            0.4 + 0.55 * agreeing as f32 / others.len() as f32,
+            format!(
+                "exactly 20 spaces before the first newline, and on {agreeing} of {} following lines",
+                others.len()
+            ),
+        )
+    };
+    Some(Match {
+        file_type: FileType::STATS,
+        confidence,
+        rule,
+    })
+}
+
+/// Map info files have twelve 0xFF bytes from offset 12, and repeat the map ID at offsets 8 and 28
+fn detect_mapinfo(data: &[u8]) -> Option<Match> {
+    if data[12..24].iter().all(|b| *b == 0xFF) && data[24] != 0xFF && data[8] == data[28] {
+        // data [8] and data[28] are expected to be the Map ID value of the map that the MAPINFO file is referencing
+        Some(Match {
+            file_type: FileType::MAPINFO,
+            // a single repeated byte is easy to hit by chance, especially when it's 0
+            confidence: if data[8] == 0 { 0.4 } else { 0.6 },
+            rule: format!(
+                "twelve 0xFF bytes at offset 12 and map ID {} repeated at offsets 8 and 28",
+                data[8]
+            ),
+        })
+    } else {
+        None
+    }
+}
+
+/// Text files decode cleanly as Shift-JIS and contain almost no control characters
+fn detect_txt(data: &[u8]) -> Option<Match> {
+    let text = plain_text(data)?;
+    // plenty of binary data decodes as Shift-JIS, so only text led by a header is taken as a text resource
+    if !text.lines().next().is_some_and(txt::is_header) {
+        return None;
+    }
+    let mut confidence: f32 = 0.3;
+    let mut rule =
+        "starts with a header and decodes as Shift-JIS with few control characters".to_string();
+    if text.contains('\n') {
+        confidence += 0.2;
+        rule.push_str(", has line breaks");
+    }
+    if !text.is_ascii() {
+        // binary data rarely happens to contain valid double byte sequences
+        confidence += 0.3;
+        rule.push_str(", contains Japanese text");
+    }
+    Some(Match {
+        file_type: FileType::TXT,
+        confidence,
+        rule,
+    })
+}
+
+/// Decodes data that is Shift-JIS with almost no control characters. This alone is too easy for binary data to pass,
+/// so it's only trusted where a text resource is expected, like next to the Put2D script that references it.
+pub(crate) fn plain_text(data: &[u8]) -> Option<String> {
+    let data = txt::trim_padding(data);
+    if data.is_empty() || data.contains(&0) {
+        return None;
+    }
+    let (text, _, errors) = SHIFT_JIS.decode(data);
+    if errors {
+        return None;
+    }
+    let chars = text.chars().count();
+    let controls = text
+        .chars()
+        .filter(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+        .count();
+    (controls * 50 <= chars).then(|| text.into_owned())
+}
+
+/// Shows a signature as text when it's printable, and as hex otherwise
+fn describe_bytes(bytes: &[u8]) -> String {
+    if bytes.iter().all(|b| b.is_ascii_graphic()) {
+        format!("`{}`", String::from_utf8_lossy(bytes))
+    } else {
+        bytes
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (priority {}, confidence {:.0}%): {}",
+            self.detector,
+            self.priority,
+            self.detected.confidence * 100.0,
+            self.detected.rule
+        )
+    }
+}
+
+impl fmt::Display for Detection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.chosen {
+            Some(chosen) => writeln!(f, "Detected by {chosen}")?,
+            None => writeln!(f, "No detector recognized the file")?,
+        }
+        for candidate in &self.runners_up {
+            writeln!(f, "  also matched {candidate}")?;
+        }
+        if !self.too_short.is_empty() {
+            writeln!(f, "  too short for {}", self.too_short.join(", "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+use super::{decode::Decode, FileType};
+use crate::errors::ValidationError;
+
+const SIGNATURE: &[u8] = b"font-type";
+
+/// A Font Type Information file. Its layout after the signature hasn't been worked out yet.
+#[derive(Debug, PartialEq)]
+pub struct Fti {
+    pub data: Vec<u8>,
+}
+
+impl Decode for Fti {
+    fn accepts(file_type: &FileType) -> bool {
+        matches!(file_type, FileType::FTI)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ValidationError> {
+        if !data.starts_with(SIGNATURE) {
+            return Err(ValidationError::IncorrectFormat("signature".to_string()));
+        }
+        Ok(Self {
+            data: data.to_vec(),
+        })
+    }
+}
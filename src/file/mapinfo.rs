@@ -0,0 +1,29 @@
+use super::{decode::Decode, FileType};
+use crate::errors::ValidationError;
+
+/// A Map Info file. Only the map ID is understood so far.
+#[derive(Debug, PartialEq)]
+pub struct MapInfo {
+    /// The ID of the map the file describes, stored at offsets 8 and 28
+    pub map_id: u8,
+    pub data: Vec<u8>,
+}
+
+impl Decode for MapInfo {
+    fn accepts(file_type: &FileType) -> bool {
+        matches!(file_type, FileType::MAPINFO)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ValidationError> {
+        if data.len() < 30 {
+            return Err(ValidationError::MissingField("map ID".to_string()));
+        }
+        if data[8] != data[28] {
+            return Err(ValidationError::IncorrectFormat("map ID".to_string()));
+        }
+        Ok(Self {
+            map_id: data[8],
+            data: data.to_vec(),
+        })
+    }
+}
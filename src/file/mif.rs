@@ -0,0 +1,54 @@
+use encoding_rs::SHIFT_JIS;
+
+use super::{decode::Decode, txt::trim_padding, FileType};
+use crate::errors::ValidationError;
+
+/// A MIF data script (fixed, attached, postbl or comid). These are line based text, with a `mif-<kind>-data-script`
+/// header line.
+#[derive(Debug, PartialEq)]
+pub struct MifScript {
+    /// The kind named in the header, such as `fixed`
+    pub kind: String,
+    /// Every line after the header
+    pub lines: Vec<String>,
+}
+
+impl MifScript {
+    /// Paths embedded in the script, such as the dev paths of the files it was built from
+    pub fn paths(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .flat_map(|line| line.split([' ', '\t', ',']))
+            .filter(|token| token.contains(['/', '\\']) && token.contains('.'))
+            .collect()
+    }
+}
+
+impl Decode for MifScript {
+    fn accepts(file_type: &FileType) -> bool {
+        matches!(
+            file_type,
+            FileType::FIXED | FileType::ATTACHED | FileType::POSTBL | FileType::COMID
+        )
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ValidationError> {
+        let (res, _, errors) = SHIFT_JIS.decode(trim_padding(data));
+        if errors {
+            return Err(ValidationError::IncorrectFormat(
+                "Invalid SHIFT_JIS encoding!".to_string(),
+            ));
+        }
+        let mut lines = res.lines();
+        let kind = lines
+            .next()
+            .and_then(|header| header.strip_prefix("mif-"))
+            .and_then(|header| header.strip_suffix("-data-script"))
+            .ok_or_else(|| ValidationError::IncorrectFormat("header".to_string()))?
+            .to_string();
+        Ok(Self {
+            kind,
+            lines: lines.map(str::to_string).collect(),
+        })
+    }
+}
@@ -1,3 +1,7 @@
+use super::{decode::Decode, FileType};
+use crate::errors::ValidationError;
+
+#[derive(Debug)]
 pub struct Name {
     pub file_path: String,
     pub name_count: u16,
@@ -5,20 +9,46 @@ pub struct Name {
 }
 
 impl Name {
+    /// Panics on a malformed script
+    #[deprecated(
+        note = "use `Name::from_data`, which reports malformed scripts instead of panicking"
+    )]
     pub fn new(data: &[u8]) -> Self {
-        let binding = String::from_utf8(data.to_vec()).unwrap();
+        Self::from_data(data).unwrap()
+    }
+
+    pub fn from_data(data: &[u8]) -> Result<Self, ValidationError> {
+        let binding = String::from_utf8(data.to_vec())
+            .map_err(|_| ValidationError::IncorrectFormat("name script".to_string()))?;
         let mut lines = binding.lines();
         lines.next();
-        let file_path = lines.next().unwrap().to_string();
-        let name_count: u16 = lines.next().unwrap().parse().unwrap();
+        let mut next_line = |field: &str| {
+            lines
+                .next()
+                .ok_or_else(|| ValidationError::MissingField(field.to_string()))
+        };
+        let file_path = next_line("file_path")?.to_string();
+        let name_count: u16 = next_line("name_count")?
+            .parse()
+            .map_err(|_| ValidationError::IncorrectFormat("name_count".to_string()))?;
         let mut names: Vec<String> = vec![];
         for _ in 0..name_count {
-            names.push(lines.next().unwrap().to_string());
+            names.push(next_line("names")?.to_string());
         }
-        Self {
+        Ok(Self {
             file_path,
             name_count,
             names,
-        }
+        })
+    }
+}
+
+impl Decode for Name {
+    fn accepts(file_type: &FileType) -> bool {
+        matches!(file_type, FileType::NAME)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ValidationError> {
+        Self::from_data(data)
     }
 }
@@ -0,0 +1,38 @@
+use super::{decode::Decode, FileType};
+use crate::errors::ValidationError;
+
+const SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// A PNG image, with the fields of its IHDR chunk
+#[derive(Debug, PartialEq)]
+pub struct Png {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pub data: Vec<u8>,
+}
+
+impl Decode for Png {
+    fn accepts(file_type: &FileType) -> bool {
+        matches!(file_type, FileType::PNG)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ValidationError> {
+        if !data.starts_with(&SIGNATURE) {
+            return Err(ValidationError::IncorrectFormat("signature".to_string()));
+        }
+        // the IHDR chunk always comes first: a u32 length, `IHDR`, then the header fields
+        if data.len() < 26 || data[12..16] != *b"IHDR" {
+            return Err(ValidationError::MissingField("IHDR".to_string()));
+        }
+        let word = |i: usize| u32::from_be_bytes(data[i..i + 4].try_into().unwrap());
+        Ok(Self {
+            width: word(16),
+            height: word(20),
+            bit_depth: data[24],
+            color_type: data[25],
+            data: data.to_vec(),
+        })
+    }
+}
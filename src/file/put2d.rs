@@ -1,22 +1,51 @@
-#![allow(dead_code)]
-
 use encoding_rs::SHIFT_JIS;
-use std::str::FromStr;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, str::FromStr};
 
+use super::{decode::Decode, FileType};
 use crate::errors::ValidationError;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Put2D {
     pub id: u16,
     pub unknown: u8,
     pub txd_path: String,
     pub txt_path: String,
+    /// The number of entries the script declared. [`Put2D::to_bytes`] writes the length of `entries` instead.
     pub entry_count: usize,
     pub entries: Vec<Put2DEntry>,
+    /// Everything after the last entry, split on `\n` like the rest of the script. Usually a single empty line
+    /// left by the final newline.
+    pub trailing_lines: Vec<String>,
+    /// The six header lines as they were read. Only written to documents when a line isn't written the way its value
+    /// normally would be.
+    #[serde(
+        default,
+        skip_serializing_if = "RawToken::all_regular",
+        serialize_with = "serialize_raw",
+        deserialize_with = "deserialize_raw_header"
+    )]
+    raw_header: Vec<RawToken>,
 }
 
+const HEADER: &str = "put2d-script";
+
 impl Put2D {
     pub fn from_data(data: &[u8]) -> Result<Self, ValidationError> {
+        let (put2d_script, diagnostics) = Self::read(data, false)?;
+        match diagnostics.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(put2d_script),
+        }
+    }
+
+    /// Parses a script, skipping entries that can't be parsed rather than failing. Every skipped entry is reported,
+    /// and `entry_count` is set to the number of entries that were kept. Only a malformed header is an error.
+    pub fn from_data_lenient(data: &[u8]) -> Result<(Self, Vec<ValidationError>), ValidationError> {
+        Self::read(data, true)
+    }
+
+    fn read(data: &[u8], lenient: bool) -> Result<(Self, Vec<ValidationError>), ValidationError> {
         let (res, _, errors) = SHIFT_JIS.decode(data);
         if errors {
             return Err(ValidationError::IncorrectFormat(
@@ -25,230 +54,893 @@ impl Put2D {
         }
         let data = res.into_owned();
         let split: Vec<&str> = data.split('\n').collect();
+        // header lines are single tokens, since paths may contain spaces
+        let line = |i: usize| Tokens {
+            line: i + 1,
+            offset: 0,
+            tokens: split.get(i).into_iter().copied().collect(),
+        };
 
-        let entry_count: usize = split[5].parse().unwrap();
+        let entry_count: usize = line(5).parse(0)?;
+        // the empty line left by the final newline is never an entry
+        let available = split
+            .len()
+            .saturating_sub(6 + usize::from(split.last() == Some(&"")));
+        let end = 6 + entry_count.min(available);
+        // a count running past the end of the script is reported once, at the first missing line
+        let missing = || ValidationError::MissingToken {
+            line: end + 1,
+            field: 0,
+            expected: "entry",
+        };
+        if entry_count > available && !lenient {
+            return Err(missing());
+        }
         let mut entries: Vec<Put2DEntry> = vec![];
-        let mut i: usize = 0;
-
-        while i < entry_count {
-            entries.push(split[6 + i].parse()?);
-            i += 1;
+        let mut diagnostics = vec![];
+        for (i, entry) in split.iter().enumerate().take(end).skip(6) {
+            match Put2DEntry::parse(entry, i + 1) {
+                Ok(entry) => entries.push(entry),
+                Err(error) => {
+                    diagnostics.push(error);
+                    if !lenient {
+                        break;
+                    }
+                }
+            }
+        }
+        if entry_count > available {
+            diagnostics.push(missing());
         }
 
-        Ok(Put2D {
-            id: split[1].parse().unwrap(),
-            unknown: split[2].parse().unwrap(),
-            txd_path: split[3].to_string(),
-            txt_path: split[4].to_string(),
+        let mut put2d_script = Put2D {
+            id: line(1).parse(0)?,
+            unknown: line(2).parse(0)?,
+            txd_path: line(3).text(0)?.to_string(),
+            txt_path: line(4).text(0)?.to_string(),
             entry_count,
             entries,
+            trailing_lines: split
+                .iter()
+                .skip(end)
+                .map(|line| line.to_string())
+                .collect(),
+            raw_header: vec![],
+        };
+        // paired with the declared count, so a count corrected below is written out rather than the original text
+        put2d_script.raw_header = RawToken::pair(
+            &split[..6],
+            put2d_script.header_lines(put2d_script.entry_count),
+        );
+        if lenient {
+            put2d_script.entry_count = put2d_script.entries.len();
+        }
+        Ok((put2d_script, diagnostics))
+    }
+
+    fn header_lines(&self, entry_count: usize) -> Vec<String> {
+        vec![
+            HEADER.to_string(),
+            self.id.to_string(),
+            self.unknown.to_string(),
+            self.txd_path.clone(),
+            self.txt_path.clone(),
+            entry_count.to_string(),
+        ]
+    }
+
+    /// The six header lines as [`Put2D::to_bytes`] writes them, keeping the original text of unchanged values
+    pub fn header_text(&self) -> Vec<String> {
+        RawToken::merge(self.header_lines(self.entries.len()), &self.raw_header)
+    }
+
+    /// Writes the script back out as Shift-JIS. Unchanged scripts come back byte for byte as [`Put2D::from_data`]
+    /// read them, and unchanged fields of edited scripts keep their original text. The entry count is always written
+    /// as the number of entries, so `entries` can be edited directly.
+    ///
+    /// Fails on text Shift-JIS can't represent. The NEC-selected IBM extension characters are always written in
+    /// their IBM form, so a script using the NEC codes for them won't come back identical.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ValidationError> {
+        let mut lines = self.header_text();
+        lines.extend(self.entries.iter().map(Put2DEntry::to_string));
+        lines.extend(self.trailing_lines.iter().cloned());
+        encode(&lines.join("\n"))
+    }
+
+    /// The dev path and name of the image each Type1 and Type2 entry was built from
+    pub fn images(&self) -> Vec<(&str, &str)> {
+        self.entries
+            .iter()
+            .filter_map(|e| e.file_path().zip(e.file_name()))
+            .collect()
+    }
+
+    pub fn entry_by_id(&self, id: u8) -> Option<&Put2DEntry> {
+        self.entries.iter().find(|e| e.id() == Some(id))
+    }
+
+    pub fn entry_by_id_mut(&mut self, id: u8) -> Option<&mut Put2DEntry> {
+        self.entries.iter_mut().find(|e| e.id() == Some(id))
+    }
+
+    pub fn entry_by_file_name(&self, file_name: &str) -> Option<&Put2DEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.file_name() == Some(file_name))
+    }
+
+    pub fn entry_by_file_name_mut(&mut self, file_name: &str) -> Option<&mut Put2DEntry> {
+        self.entries
+            .iter_mut()
+            .find(|e| e.file_name() == Some(file_name))
+    }
+
+    /// Adds an entry at `index`, shifting the ones after it down. The helpers that add, remove and move entries keep
+    /// `entry_count` in sync.
+    pub fn insert_entry(&mut self, index: usize, entry: Put2DEntry) {
+        self.entries.insert(index, entry);
+        self.entry_count = self.entries.len();
+    }
+
+    pub fn push_entry(&mut self, entry: Put2DEntry) {
+        self.insert_entry(self.entries.len(), entry);
+    }
+
+    pub fn remove_entry(&mut self, index: usize) -> Put2DEntry {
+        let entry = self.entries.remove(index);
+        self.entry_count = self.entries.len();
+        entry
+    }
+
+    /// Moves an entry to `to`, such as to draw it above or below the others
+    pub fn move_entry(&mut self, from: usize, to: usize) {
+        let entry = self.remove_entry(from);
+        self.insert_entry(to, entry);
+    }
+
+    pub fn swap_entries(&mut self, a: usize, b: usize) {
+        self.entries.swap(a, b);
+    }
+}
+
+/// The space-separated tokens of one line, which report parse failures by line and field
+struct Tokens<'a> {
+    line: usize,
+    /// Added to the index of each token when reporting it, for tokens that don't start at the beginning of the line
+    offset: usize,
+    tokens: Vec<&'a str>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(line_text: &'a str, line: usize, offset: usize) -> Self {
+        Self {
+            line,
+            offset,
+            tokens: line_text.split(' ').collect(),
+        }
+    }
+
+    fn text(&self, field: usize) -> Result<&'a str, ValidationError> {
+        self.tokens
+            .get(field)
+            .copied()
+            .ok_or(ValidationError::MissingToken {
+                line: self.line,
+                field: field + self.offset,
+                expected: "text",
+            })
+    }
+
+    fn parse<T: FromStr>(&self, field: usize) -> Result<T, ValidationError> {
+        let token = self
+            .tokens
+            .get(field)
+            .ok_or(ValidationError::MissingToken {
+                line: self.line,
+                field: field + self.offset,
+                expected: std::any::type_name::<T>(),
+            })?;
+        token.parse().map_err(|_| ValidationError::InvalidToken {
+            line: self.line,
+            field: field + self.offset,
+            expected: std::any::type_name::<T>(),
+            token: token.to_string(),
         })
     }
+
+    /// For fields known to hold malformed values in some retail scripts, which are read as the default
+    fn parse_or_default<T: FromStr + Default>(&self, field: usize) -> Result<T, ValidationError> {
+        match self.parse(field) {
+            Err(ValidationError::InvalidToken { .. }) => Ok(T::default()),
+            parsed => parsed,
+        }
+    }
 }
 
-#[derive(Debug)]
+/// A token or line as it was read, kept so that fields which haven't changed since are written back verbatim. This
+/// preserves things like leading zeros, malformed values that were read as a default, and extra trailing tokens.
+#[derive(Debug, Clone, PartialEq)]
+struct RawToken {
+    text: String,
+    /// How the value parsed from the text was written at the time, if the token was parsed at all
+    parsed: Option<String>,
+}
+
+impl RawToken {
+    fn pair(raw: &[&str], parsed: Vec<String>) -> Vec<Self> {
+        let mut parsed = parsed.into_iter();
+        raw.iter()
+            .map(|text| Self {
+                text: text.to_string(),
+                parsed: parsed.next(),
+            })
+            .collect()
+    }
+
+    /// Swaps each current value for its raw text if the value is unchanged, then appends any unparsed tokens
+    fn merge(current: Vec<String>, raw: &[Self]) -> Vec<String> {
+        let len = current.len();
+        let mut merged: Vec<String> = current
+            .into_iter()
+            .zip(raw.iter().map(Some).chain(std::iter::repeat(None)))
+            .map(|(value, raw)| match raw {
+                Some(raw) if raw.parsed.as_ref() == Some(&value) => raw.text.clone(),
+                _ => value,
+            })
+            .collect();
+        merged.extend(
+            raw.iter()
+                .skip(len)
+                .filter(|raw| raw.parsed.is_none())
+                .map(|raw| raw.text.clone()),
+        );
+        merged
+    }
+
+    /// Whether every token was parsed and is written just as its value would be, so it needn't be kept
+    fn all_regular(raw: &[Self]) -> bool {
+        raw.iter().all(|r| r.parsed.as_ref() == Some(&r.text))
+    }
+}
+
+fn serialize_raw<S: Serializer>(raw: &[RawToken], serializer: S) -> Result<S::Ok, S::Error> {
+    raw.iter()
+        .map(|r| r.text.as_str())
+        .collect::<Vec<_>>()
+        .serialize(serializer)
+}
+
+fn serialize_raw_line<S: Serializer>(raw: &[RawToken], serializer: S) -> Result<S::Ok, S::Error> {
+    let tokens: Vec<&str> = raw.iter().map(|r| r.text.as_str()).collect();
+    tokens.join(" ").serialize(serializer)
+}
+
+/// Parses the header lines again to learn how each value would normally be written
+fn deserialize_raw_header<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<RawToken>, D::Error> {
+    let lines: Vec<String> = Vec::deserialize(deserializer)?;
+    let parsed = |i: usize, text: &str| match i {
+        0 => Some(HEADER.to_string()),
+        1 => text.parse::<u16>().ok().map(|v| v.to_string()),
+        2 => text.parse::<u8>().ok().map(|v| v.to_string()),
+        5 => text.parse::<usize>().ok().map(|v| v.to_string()),
+        _ => Some(text.to_string()),
+    };
+    Ok(lines
+        .iter()
+        .enumerate()
+        .map(|(i, text)| RawToken {
+            text: text.clone(),
+            parsed: parsed(i, text),
+        })
+        .collect())
+}
+
+fn deserialize_raw_entry<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<RawToken>, D::Error> {
+    let line = String::deserialize(deserializer)?;
+    Put2DEntry::parse(&line, 1)
+        .map(|entry| entry.raw)
+        .map_err(D::Error::custom)
+}
+
+/// Encodes text as Shift-JIS, naming the first character that can't be
+fn encode(text: &str) -> Result<Vec<u8>, ValidationError> {
+    let (res, _, errors) = SHIFT_JIS.encode(text);
+    if errors {
+        let mut buf = [0; 4];
+        let unencodable = text
+            .chars()
+            .find(|c| SHIFT_JIS.encode(c.encode_utf8(&mut buf)).2)
+            .unwrap_or_default();
+        return Err(ValidationError::Unencodable(unencodable.to_string()));
+    }
+    Ok(res.into_owned())
+}
+
+impl Decode for Put2D {
+    fn accepts(file_type: &FileType) -> bool {
+        matches!(file_type, FileType::PUT2D)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ValidationError> {
+        Self::from_data(data)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Put2DEntry {
-    unknown: u8,
-    entry_type: Put2DEntryType,
+    pub unknown: u8,
+    #[serde(flatten)]
+    pub entry_type: Put2DEntryType,
+    /// The tokens of the line the entry was read from. Documents hold the whole line, and only when a token isn't
+    /// written the way its value normally would be.
+    #[serde(
+        default,
+        skip_serializing_if = "RawToken::all_regular",
+        serialize_with = "serialize_raw_line",
+        deserialize_with = "deserialize_raw_entry"
+    )]
+    raw: Vec<RawToken>,
+}
+
+impl Put2DEntry {
+    pub fn new(unknown: u8, entry_type: Put2DEntryType) -> Self {
+        Self {
+            unknown,
+            entry_type,
+            raw: vec![],
+        }
+    }
+
+    /// The id of a Type1 or Type2 entry
+    pub fn id(&self) -> Option<u8> {
+        match &self.entry_type {
+            Put2DEntryType::TYPE1 { entry } => Some(entry.id),
+            Put2DEntryType::TYPE2 { entry } => Some(entry.id),
+            _ => None,
+        }
+    }
+
+    /// The dev path of the image a Type1 or Type2 entry was built from
+    pub fn file_path(&self) -> Option<&str> {
+        match &self.entry_type {
+            Put2DEntryType::TYPE1 { entry } => Some(&entry.file_path),
+            Put2DEntryType::TYPE2 { entry } => Some(&entry.file_path),
+            _ => None,
+        }
+    }
+
+    /// The name of the image a Type1 or Type2 entry was built from
+    pub fn file_name(&self) -> Option<&str> {
+        match &self.entry_type {
+            Put2DEntryType::TYPE1 { entry } => Some(&entry.file_name),
+            Put2DEntryType::TYPE2 { entry } => Some(&entry.file_name),
+            _ => None,
+        }
+    }
+
+    /// Points a Type1 or Type2 entry at another image, returning whether the entry has one
+    pub fn set_image(&mut self, file_path: &str, file_name: &str) -> bool {
+        let (path, name) = match &mut self.entry_type {
+            Put2DEntryType::TYPE1 { entry } => (&mut entry.file_path, &mut entry.file_name),
+            Put2DEntryType::TYPE2 { entry } => (&mut entry.file_path, &mut entry.file_name),
+            _ => return false,
+        };
+        *path = file_path.to_string();
+        *name = file_name.to_string();
+        true
+    }
+
+    /// The entry's current values, one per token
+    fn tokens(&self) -> Vec<String> {
+        format!("{} {}", self.unknown, self.entry_type)
+            .split(' ')
+            .map(str::to_string)
+            .collect()
+    }
 }
 
 impl FromStr for Put2DEntry {
     type Err = ValidationError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let split: Vec<&str> = s.split(' ').collect();
-        Ok(Put2DEntry {
-            unknown: split[0].parse().unwrap(),
-            entry_type: split[1..split.len()].join(" ").parse()?,
-        })
+        Self::parse(s, 1)
     }
 }
 
-#[derive(Debug)]
+impl Put2DEntry {
+    /// Parses line `line` of a script, numbered from 1
+    fn parse(s: &str, line: usize) -> Result<Self, ValidationError> {
+        let split = Tokens::new(s, line, 0);
+        let mut entry = Put2DEntry {
+            unknown: split.parse(0)?,
+            entry_type: Put2DEntryType::parse(&Tokens {
+                line,
+                offset: 1,
+                tokens: split.tokens[1..].to_vec(),
+            })?,
+            raw: vec![],
+        };
+        entry.raw = RawToken::pair(&split.tokens, entry.tokens());
+        Ok(entry)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum Put2DEntryType {
-    TYPE0 { entry: Type0 },
-    TYPE1 { entry: Type1 },
-    TYPE2 { entry: Type2 },
-    TYPE3 { entry: Type3 },
-    TYPE4 { entry: Type4 },
-    UNKNOWN,
+    TYPE0 {
+        entry: Type0,
+    },
+    TYPE1 {
+        entry: Type1,
+    },
+    TYPE2 {
+        entry: Type2,
+    },
+    TYPE3 {
+        entry: Type3,
+    },
+    TYPE4 {
+        entry: Type4,
+    },
+    /// An entry type that isn't understood yet, kept as its type number and the tokens that follow it
+    UNKNOWN {
+        number: String,
+        fields: Vec<String>,
+    },
+}
+
+impl Put2DEntryType {
+    /// The names of the fields following a type number, for the entry types that are understood
+    pub fn field_names(number: &str) -> Option<&'static [&'static str]> {
+        match number {
+            "0" => Some(&Type0::FIELDS),
+            "1" => Some(&Type1::FIELDS),
+            "2" => Some(&Type2::FIELDS),
+            "3" => Some(&Type3::FIELDS),
+            "4" => Some(&Type4::FIELDS),
+            _ => None,
+        }
+    }
+
+    /// The type number the entry's line starts with
+    pub fn number(&self) -> &str {
+        match self {
+            Self::TYPE0 { .. } => "0",
+            Self::TYPE1 { .. } => "1",
+            Self::TYPE2 { .. } => "2",
+            Self::TYPE3 { .. } => "3",
+            Self::TYPE4 { .. } => "4",
+            Self::UNKNOWN { number, .. } => number,
+        }
+    }
 }
 
 impl FromStr for Put2DEntryType {
     type Err = ValidationError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let split: Vec<&str> = s.split(' ').collect();
-        match split[0] {
+        Self::parse(&Tokens::new(s, 1, 0))
+    }
+}
+
+impl Put2DEntryType {
+    fn parse(split: &Tokens) -> Result<Self, ValidationError> {
+        match split.text(0)? {
             "0" => Ok(Self::TYPE0 {
                 entry: Type0 {
-                    unknown_1: split[1].parse().unwrap(),
-                    unknown_2: split[2].parse().unwrap(),
-                    content: split[3].to_string(),
-                    unknown_3: split[4].parse().unwrap(),
-                    unknown_4: split[5].parse().unwrap(),
-                    unknown_5: split[6].parse().unwrap(),
-                    unknown_6: split[7].parse().unwrap(),
-                    unknown_7: split[8].parse().unwrap(),
-                    unknown_8: split[9].parse().unwrap(),
-                    unknown_9: split[10].parse().unwrap_or_default(),
+                    unknown_1: split.parse(1)?,
+                    unknown_2: split.parse(2)?,
+                    content: split.text(3)?.to_string(),
+                    unknown_3: split.parse(4)?,
+                    unknown_4: split.parse(5)?,
+                    unknown_5: split.parse(6)?,
+                    unknown_6: split.parse(7)?,
+                    unknown_7: split.parse(8)?,
+                    unknown_8: split.parse(9)?,
+                    unknown_9: split.parse_or_default(10)?,
                 },
             }), // to handle an undocumented error in cursor.put2d
             "1" => Ok(Self::TYPE1 {
                 entry: Type1 {
-                    unknown_1: split[1].parse().unwrap(),
-                    file_path: split[2].to_string(),
-                    unknown_2: split[3].parse().unwrap(),
-                    unknown_3: split[4].parse().unwrap(),
-                    unknown_4: split[5].parse().unwrap(),
-                    unknown_5: split[6].parse().unwrap(),
-                    unknown_6: split[7].parse().unwrap(),
-                    unknown_7: split[8].parse().unwrap(),
-                    unknown_8: split[9].parse().unwrap(),
-                    unknown_9: split[10].parse().unwrap(),
-                    id: split[11].parse().unwrap(),
-                    file_name: split[12].to_string(),
+                    unknown_1: split.parse(1)?,
+                    file_path: split.text(2)?.to_string(),
+                    unknown_2: split.parse(3)?,
+                    unknown_3: split.parse(4)?,
+                    unknown_4: split.parse(5)?,
+                    unknown_5: split.parse(6)?,
+                    unknown_6: split.parse(7)?,
+                    unknown_7: split.parse(8)?,
+                    unknown_8: split.parse(9)?,
+                    unknown_9: split.parse(10)?,
+                    id: split.parse(11)?,
+                    file_name: split.text(12)?.to_string(),
                 },
             }),
             "2" => Ok(Self::TYPE2 {
                 entry: Type2 {
-                    unknown_1: split[1].parse().unwrap(),
-                    file_path: split[2].to_string(),
-                    unknown_2: split[3].parse().unwrap(),
-                    unknown_3: split[4].parse().unwrap(),
-                    unknown_4: split[5].parse().unwrap(),
-                    unknown_5: split[6].parse().unwrap(),
-                    unknown_6: split[7].parse().unwrap(),
-                    unknown_7: split[8].parse().unwrap(),
-                    unknown_8: split[9].parse().unwrap(),
-                    unknown_9: split[10].parse().unwrap(),
-                    unknown_10: split[11].parse().unwrap(),
-                    unknown_11: split[12].parse().unwrap(),
-                    unknown_12: split[13].parse().unwrap(),
-                    unknown_13: split[14].parse().unwrap(),
-                    id: split[15].parse().unwrap(),
-                    file_name: split[16].to_string(),
+                    unknown_1: split.parse(1)?,
+                    file_path: split.text(2)?.to_string(),
+                    unknown_2: split.parse(3)?,
+                    unknown_3: split.parse(4)?,
+                    unknown_4: split.parse(5)?,
+                    unknown_5: split.parse(6)?,
+                    unknown_6: split.parse(7)?,
+                    unknown_7: split.parse(8)?,
+                    unknown_8: split.parse(9)?,
+                    unknown_9: split.parse(10)?,
+                    unknown_10: split.parse(11)?,
+                    unknown_11: split.parse(12)?,
+                    unknown_12: split.parse(13)?,
+                    unknown_13: split.parse(14)?,
+                    id: split.parse(15)?,
+                    file_name: split.text(16)?.to_string(),
                 },
             }),
             "3" => Ok(Self::TYPE3 {
                 entry: Type3 {
-                    unknown_1: split[1].parse().unwrap(),
-                    unknown_2: split[2].parse().unwrap(),
-                    unknown_3: split[3].parse().unwrap(),
-                    unknown_4: split[4].parse().unwrap(),
-                    unknown_5: split[5].parse().unwrap(),
-                    unknown_6: split[6].parse().unwrap(),
-                    unknown_7: split[7].parse().unwrap(),
-                    unknown_8: split[8].parse().unwrap(),
-                    unknown_9: split[9].parse().unwrap(),
-                    unknown_10: split[10].parse().unwrap_or_default(), // to handle an undocumented error in soundtest.put2d
-                    unknown_11: split[11].parse().unwrap(),
-                    unknown_12: split[12].parse().unwrap(),
+                    unknown_1: split.parse(1)?,
+                    unknown_2: split.parse(2)?,
+                    unknown_3: split.parse(3)?,
+                    unknown_4: split.parse(4)?,
+                    unknown_5: split.parse(5)?,
+                    unknown_6: split.parse(6)?,
+                    unknown_7: split.parse(7)?,
+                    unknown_8: split.parse(8)?,
+                    unknown_9: split.parse(9)?,
+                    unknown_10: split.parse_or_default(10)?, // to handle an undocumented error in soundtest.put2d
+                    unknown_11: split.parse(11)?,
+                    unknown_12: split.parse(12)?,
                 },
             }),
             "4" => Ok(Self::TYPE4 {
                 entry: Type4 {
-                    unknown_1: split[1].parse().unwrap(),
-                    unknown_2: split[2].parse().unwrap(),
-                    unknown_3: split[3].parse().unwrap(),
-                    unknown_4: split[4].parse().unwrap(),
-                    unknown_5: split[5].parse().unwrap(),
-                    unknown_6: split[6].parse().unwrap(),
-                    unknown_7: split[7].parse().unwrap(),
-                    unknown_8: split[8].parse().unwrap(),
-                    unknown_9: split[9].parse().unwrap(),
-                    unknown_10: split[10].parse().unwrap(),
-                    unknown_11: split[11].parse().unwrap(),
-                    unknown_12: split[12].parse().unwrap(),
-                    unknown_13: split[13].parse().unwrap_or_default(), // to handle an undocumented error in sample_mix.put2d
-                    unknown_14: split[14].parse().unwrap(),
-                    unknown_15: split[15].parse().unwrap(),
+                    unknown_1: split.parse(1)?,
+                    unknown_2: split.parse(2)?,
+                    unknown_3: split.parse(3)?,
+                    unknown_4: split.parse(4)?,
+                    unknown_5: split.parse(5)?,
+                    unknown_6: split.parse(6)?,
+                    unknown_7: split.parse(7)?,
+                    unknown_8: split.parse(8)?,
+                    unknown_9: split.parse(9)?,
+                    unknown_10: split.parse(10)?,
+                    unknown_11: split.parse(11)?,
+                    unknown_12: split.parse(12)?,
+                    unknown_13: split.parse_or_default(13)?, // to handle an undocumented error in sample_mix.put2d
+                    unknown_14: split.parse(14)?,
+                    unknown_15: split.parse(15)?,
                 },
             }),
-            _ => Ok(Self::UNKNOWN),
+            number => Ok(Self::UNKNOWN {
+                number: number.to_string(),
+                fields: split.tokens[1..]
+                    .iter()
+                    .map(|field| field.to_string())
+                    .collect(),
+            }),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Type0 {
-    unknown_1: u8,
-    unknown_2: u8,
-    content: String,
-    unknown_3: u16,
-    unknown_4: u16,
-    unknown_5: u8,
-    unknown_6: u8,
-    unknown_7: u8,
-    unknown_8: u8,
-    unknown_9: u8,
-}
-
-#[derive(Debug)]
+    pub unknown_1: u8,
+    pub unknown_2: u8,
+    pub content: String,
+    pub unknown_3: u16,
+    pub unknown_4: u16,
+    pub unknown_5: u8,
+    pub unknown_6: u8,
+    pub unknown_7: u8,
+    pub unknown_8: u8,
+    pub unknown_9: u8,
+}
+
+impl Type0 {
+    /// The name of each field, in the order they're written after the type number
+    pub const FIELDS: [&'static str; 10] = [
+        "unknown_1",
+        "unknown_2",
+        "content",
+        "unknown_3",
+        "unknown_4",
+        "unknown_5",
+        "unknown_6",
+        "unknown_7",
+        "unknown_8",
+        "unknown_9",
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Type1 {
-    unknown_1: u8,
-    file_path: String,
-    unknown_2: u16,
-    unknown_3: u16,
-    unknown_4: u16,
-    unknown_5: u8,
-    unknown_6: u8,
-    unknown_7: u8,
-    unknown_8: u8,
-    unknown_9: u8,
-    id: u8,
-    file_name: String,
-}
-
-#[derive(Debug)]
+    pub unknown_1: u8,
+    pub file_path: String,
+    pub unknown_2: u16,
+    pub unknown_3: u16,
+    pub unknown_4: u16,
+    pub unknown_5: u8,
+    pub unknown_6: u8,
+    pub unknown_7: u8,
+    pub unknown_8: u8,
+    pub unknown_9: u8,
+    pub id: u8,
+    pub file_name: String,
+}
+
+impl Type1 {
+    /// The name of each field, in the order they're written after the type number
+    pub const FIELDS: [&'static str; 12] = [
+        "unknown_1",
+        "file_path",
+        "unknown_2",
+        "unknown_3",
+        "unknown_4",
+        "unknown_5",
+        "unknown_6",
+        "unknown_7",
+        "unknown_8",
+        "unknown_9",
+        "id",
+        "file_name",
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Type2 {
-    unknown_1: u8,
-    file_path: String,
-    unknown_2: u16,
-    unknown_3: u16,
-    unknown_4: u16,
-    unknown_5: u16,
-    unknown_6: u16,
-    unknown_7: u16,
-    unknown_8: u16,
-    unknown_9: u8,
-    unknown_10: u8,
-    unknown_11: u8,
-    unknown_12: u8,
-    unknown_13: u8,
-    id: u8,
-    file_name: String,
-}
-
-#[derive(Debug)]
+    pub unknown_1: u8,
+    pub file_path: String,
+    pub unknown_2: u16,
+    pub unknown_3: u16,
+    pub unknown_4: u16,
+    pub unknown_5: u16,
+    pub unknown_6: u16,
+    pub unknown_7: u16,
+    pub unknown_8: u16,
+    pub unknown_9: u8,
+    pub unknown_10: u8,
+    pub unknown_11: u8,
+    pub unknown_12: u8,
+    pub unknown_13: u8,
+    pub id: u8,
+    pub file_name: String,
+}
+
+impl Type2 {
+    /// The name of each field, in the order they're written after the type number
+    pub const FIELDS: [&'static str; 16] = [
+        "unknown_1",
+        "file_path",
+        "unknown_2",
+        "unknown_3",
+        "unknown_4",
+        "unknown_5",
+        "unknown_6",
+        "unknown_7",
+        "unknown_8",
+        "unknown_9",
+        "unknown_10",
+        "unknown_11",
+        "unknown_12",
+        "unknown_13",
+        "id",
+        "file_name",
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Type3 {
-    unknown_1: u8,
-    unknown_2: u8,
-    unknown_3: u8,
-    unknown_4: u8,
-    unknown_5: u16,
-    unknown_6: u16,
-    unknown_7: u8,
-    unknown_8: u8,
-    unknown_9: u8,
-    unknown_10: u8,
-    unknown_11: u8,
-    unknown_12: u8,
-}
-
-#[derive(Debug)]
+    pub unknown_1: u8,
+    pub unknown_2: u8,
+    pub unknown_3: u8,
+    pub unknown_4: u8,
+    pub unknown_5: u16,
+    pub unknown_6: u16,
+    pub unknown_7: u8,
+    pub unknown_8: u8,
+    pub unknown_9: u8,
+    pub unknown_10: u8,
+    pub unknown_11: u8,
+    pub unknown_12: u8,
+}
+
+impl Type3 {
+    /// The name of each field, in the order they're written after the type number
+    pub const FIELDS: [&'static str; 12] = [
+        "unknown_1",
+        "unknown_2",
+        "unknown_3",
+        "unknown_4",
+        "unknown_5",
+        "unknown_6",
+        "unknown_7",
+        "unknown_8",
+        "unknown_9",
+        "unknown_10",
+        "unknown_11",
+        "unknown_12",
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Type4 {
-    unknown_1: u8,
-    unknown_2: u16,
-    unknown_3: u16,
-    unknown_4: u16,
-    unknown_5: u16,
-    unknown_6: u16,
-    unknown_7: u8,
-    unknown_8: u8,
-    unknown_9: u8,
-    unknown_10: u8,
-    unknown_11: u8,
-    unknown_12: u16,
-    unknown_13: u8,
-    unknown_14: u8,
-    unknown_15: u8,
+    pub unknown_1: u8,
+    pub unknown_2: u16,
+    pub unknown_3: u16,
+    pub unknown_4: u16,
+    pub unknown_5: u16,
+    pub unknown_6: u16,
+    pub unknown_7: u8,
+    pub unknown_8: u8,
+    pub unknown_9: u8,
+    pub unknown_10: u8,
+    pub unknown_11: u8,
+    pub unknown_12: u16,
+    pub unknown_13: u8,
+    pub unknown_14: u8,
+    pub unknown_15: u8,
+}
+
+impl Type4 {
+    /// The name of each field, in the order they're written after the type number
+    pub const FIELDS: [&'static str; 15] = [
+        "unknown_1",
+        "unknown_2",
+        "unknown_3",
+        "unknown_4",
+        "unknown_5",
+        "unknown_6",
+        "unknown_7",
+        "unknown_8",
+        "unknown_9",
+        "unknown_10",
+        "unknown_11",
+        "unknown_12",
+        "unknown_13",
+        "unknown_14",
+        "unknown_15",
+    ];
+}
+
+impl fmt::Display for Put2DEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // raw tokens from before the entry changed type don't line up with its fields
+        let raw = match self.raw.get(1).and_then(|r| r.parsed.as_deref()) {
+            Some(number) if number == self.entry_type.number() => &self.raw[..],
+            _ => &[],
+        };
+        write!(f, "{}", RawToken::merge(self.tokens(), raw).join(" "))
+    }
+}
+
+impl fmt::Display for Put2DEntryType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TYPE0 { entry } => write!(f, "0 {entry}"),
+            Self::TYPE1 { entry } => write!(f, "1 {entry}"),
+            Self::TYPE2 { entry } => write!(f, "2 {entry}"),
+            Self::TYPE3 { entry } => write!(f, "3 {entry}"),
+            Self::TYPE4 { entry } => write!(f, "4 {entry}"),
+            Self::UNKNOWN { number, fields } => {
+                write!(f, "{number}")?;
+                for field in fields {
+                    write!(f, " {field}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Type0 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {} {} {}",
+            self.unknown_1,
+            self.unknown_2,
+            self.content,
+            self.unknown_3,
+            self.unknown_4,
+            self.unknown_5,
+            self.unknown_6,
+            self.unknown_7,
+            self.unknown_8,
+            self.unknown_9
+        )
+    }
+}
+
+impl fmt::Display for Type1 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {} {} {} {} {}",
+            self.unknown_1,
+            self.file_path,
+            self.unknown_2,
+            self.unknown_3,
+            self.unknown_4,
+            self.unknown_5,
+            self.unknown_6,
+            self.unknown_7,
+            self.unknown_8,
+            self.unknown_9,
+            self.id,
+            self.file_name
+        )
+    }
+}
+
+impl fmt::Display for Type2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+            self.unknown_1,
+            self.file_path,
+            self.unknown_2,
+            self.unknown_3,
+            self.unknown_4,
+            self.unknown_5,
+            self.unknown_6,
+            self.unknown_7,
+            self.unknown_8,
+            self.unknown_9,
+            self.unknown_10,
+            self.unknown_11,
+            self.unknown_12,
+            self.unknown_13,
+            self.id,
+            self.file_name
+        )
+    }
+}
+
+impl fmt::Display for Type3 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {} {} {} {} {}",
+            self.unknown_1,
+            self.unknown_2,
+            self.unknown_3,
+            self.unknown_4,
+            self.unknown_5,
+            self.unknown_6,
+            self.unknown_7,
+            self.unknown_8,
+            self.unknown_9,
+            self.unknown_10,
+            self.unknown_11,
+            self.unknown_12
+        )
+    }
+}
+
+impl fmt::Display for Type4 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+            self.unknown_1,
+            self.unknown_2,
+            self.unknown_3,
+            self.unknown_4,
+            self.unknown_5,
+            self.unknown_6,
+            self.unknown_7,
+            self.unknown_8,
+            self.unknown_9,
+            self.unknown_10,
+            self.unknown_11,
+            self.unknown_12,
+            self.unknown_13,
+            self.unknown_14,
+            self.unknown_15
+        )
+    }
 }
@@ -0,0 +1,170 @@
+use super::{decode::Decode, FileType};
+use crate::errors::ValidationError;
+
+const STRING: u32 = 0x02;
+const EXTENSION: u32 = 0x03;
+const TEXTURE: u32 = 0x06;
+const MATERIAL: u32 = 0x07;
+const MATERIAL_LIST: u32 = 0x08;
+const GEOMETRY: u32 = 0x0F;
+const CLUMP: u32 = 0x10;
+const FRAME_LIST: u32 = 0x0E;
+const ATOMIC: u32 = 0x14;
+const TEXTURE_NATIVE: u32 = 0x15;
+const TEXTURE_DICTIONARY: u32 = 0x16;
+const GEOMETRY_LIST: u32 = 0x1A;
+/// The plugin holding a frame's name, within the frame's extension
+const FRAME_NAME: u32 = 0x0253F2FE;
+/// Chunks made up entirely of child chunks
+const CONTAINERS: [u32; 11] = [
+    EXTENSION,
+    TEXTURE,
+    MATERIAL,
+    MATERIAL_LIST,
+    GEOMETRY,
+    CLUMP,
+    FRAME_LIST,
+    ATOMIC,
+    TEXTURE_NATIVE,
+    TEXTURE_DICTIONARY,
+    GEOMETRY_LIST,
+];
+
+/// A RenderWare chunk: a u32 chunk ID, the u32 size of its data and a u32 library version stamp, followed by the data
+#[derive(Debug, PartialEq)]
+pub struct Chunk {
+    pub id: u32,
+    pub version: u32,
+    pub data: Vec<u8>,
+}
+
+impl Chunk {
+    /// Reads the chunk at the start of `data`, returning it along with whatever follows it
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8]), ValidationError> {
+        if data.len() < 12 {
+            return Err(ValidationError::MissingField("chunk header".to_string()));
+        }
+        let word = |i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+        let size = word(4) as usize;
+        if size > data.len() - 12 {
+            return Err(ValidationError::IncorrectFormat("chunk size".to_string()));
+        }
+        Ok((
+            Self {
+                id: word(0),
+                version: word(8),
+                data: data[12..12 + size].to_vec(),
+            },
+            &data[12 + size..],
+        ))
+    }
+
+    /// Parses the chunk's data as a sequence of child chunks
+    pub fn children(&self) -> Result<Vec<Chunk>, ValidationError> {
+        let mut children = vec![];
+        let mut rest = &self.data[..];
+        while !rest.is_empty() {
+            let (child, next) = Chunk::parse(rest)?;
+            children.push(child);
+            rest = next;
+        }
+        Ok(children)
+    }
+
+    /// Every chunk with the given ID nested within this one, depth first. Containers whose children don't parse are
+    /// skipped rather than failing the whole search.
+    pub fn find(&self, id: u32) -> Vec<Chunk> {
+        let mut found = vec![];
+        if CONTAINERS.contains(&self.id) {
+            for child in self.children().unwrap_or_default() {
+                let nested = child.find(id);
+                if child.id == id {
+                    found.push(child);
+                }
+                found.extend(nested);
+            }
+        }
+        found
+    }
+
+    /// The text of a string chunk or frame name, which may be null terminated and padded
+    pub fn text(&self) -> Option<String> {
+        let end = self
+            .data
+            .iter()
+            .position(|b| *b == 0)
+            .unwrap_or(self.data.len());
+        String::from_utf8(self.data[..end].to_vec())
+            .ok()
+            .filter(|t| !t.is_empty())
+    }
+}
+
+/// A RenderWare file whose root chunk has the ID `ID`
+#[derive(Debug, PartialEq)]
+pub struct RenderWare<const ID: u32> {
+    pub root: Chunk,
+}
+
+/// RenderWare Model File (Clump)
+pub type Dff = RenderWare<0x10>;
+/// RenderWare Texture Dictionary
+pub type Txd = RenderWare<0x16>;
+/// RenderWare Anim Animation File
+pub type Anm = RenderWare<0x1B>;
+/// RenderWare Delta Morph Animation File
+pub type Dma = RenderWare<0x1E>;
+
+impl Dff {
+    /// The names of the model's frames
+    pub fn frame_names(&self) -> Vec<String> {
+        self.root
+            .find(FRAME_NAME)
+            .iter()
+            .filter_map(Chunk::text)
+            .collect()
+    }
+
+    /// The names of the textures the model's materials use
+    pub fn texture_names(&self) -> Vec<String> {
+        texture_names(&self.root, TEXTURE)
+    }
+}
+
+impl Txd {
+    /// The names of the textures in the dictionary
+    pub fn texture_names(&self) -> Vec<String> {
+        texture_names(&self.root, TEXTURE_NATIVE)
+    }
+}
+
+/// Texture chunks hold a texture name followed by an optional mask name, so only the first string of each is kept
+fn texture_names(root: &Chunk, texture_id: u32) -> Vec<String> {
+    root.find(texture_id)
+        .iter()
+        .filter_map(|texture| {
+            let children = texture.children().ok()?;
+            children.iter().find(|c| c.id == STRING)?.text()
+        })
+        .collect()
+}
+
+impl<const ID: u32> Decode for RenderWare<ID> {
+    fn accepts(file_type: &FileType) -> bool {
+        matches!(
+            (ID, file_type),
+            (0x10, FileType::DFF)
+                | (0x16, FileType::TXD)
+                | (0x1B, FileType::ANM)
+                | (0x1E, FileType::DMA)
+        )
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ValidationError> {
+        let (root, _) = Chunk::parse(data)?;
+        if root.id != ID {
+            return Err(ValidationError::IncorrectFormat("chunk ID".to_string()));
+        }
+        Ok(Self { root })
+    }
+}
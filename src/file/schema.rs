@@ -0,0 +1,445 @@
+//! Names, units and value meanings for fields whose purpose has been worked out since the parsers were written, so
+//! they can be shared as data instead of changes to the crate.
+//!
+//! Put2D fields are addressed by entry type number and by the position of their token on the entry's line. Position
+//! 0 is the token every entry starts with, and position 1 is the type number, which can't be named, so position 2 is
+//! a Type1 entry's `unknown_1` and position 3 its `file_path`. Entry types the parser doesn't understand can be named
+//! the same way. Stats fields are addressed by column, counting the player's name as column 0. Every part of a field
+//! but its name is optional, and a name can't be one the parser already gives another field.
+//!
+//! ```json
+//! {
+//!     "put2d": {
+//!         "1": {
+//!             "4": { "name": "x", "unit": "px" },
+//!             "9": { "name": "blend", "values": { "0": "normal", "1": "additive" } }
+//!         },
+//!         "7": {
+//!             "0": { "name": "layer" },
+//!             "2": { "name": "frame_count" }
+//!         }
+//!     },
+//!     "stats": {
+//!         "13": { "name": "handedness", "values": { "0": "right", "1": "left" } }
+//!     }
+//! }
+//! ```
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, fs,
+    path::Path,
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{
+    put2d::{Put2DEntry, Put2DEntryType},
+    stats::StatsEntry,
+};
+use crate::errors::SchemaError;
+
+const BUILTIN_SCHEMA: &str = include_str!("field_schema.json");
+
+/// The position of the token every Put2D entry starts with
+const LEADING_POSITION: usize = 0;
+/// The position of a Put2D entry's type number
+const TYPE_POSITION: usize = 1;
+/// The keys a Put2D entry is written with in documents, besides its leading token
+const ENTRY_KEYS: [&str; 5] = ["type", "entry", "number", "fields", "raw"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FieldSchema {
+    /// Fields by entry type number, then position
+    #[serde(default)]
+    pub put2d: BTreeMap<String, BTreeMap<usize, FieldInfo>>,
+    /// Fields by column
+    #[serde(default)]
+    pub stats: BTreeMap<usize, FieldInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldInfo {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// What each raw value means
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub values: BTreeMap<String, String>,
+}
+
+/// A field's value as written, along with the name and meaning the schema gives it
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedField {
+    pub position: usize,
+    pub name: String,
+    pub value: String,
+    pub unit: Option<String>,
+    /// The schema's meaning for the value, if it has one
+    pub meaning: Option<String>,
+}
+
+impl FieldSchema {
+    /// The schema bundled with the toolbox, from `src/file/field_schema.json`
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: OnceLock<FieldSchema> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            let schema: FieldSchema =
+                serde_json::from_str(BUILTIN_SCHEMA).expect("builtin field schema is invalid");
+            schema.validate().expect("builtin field schema is invalid");
+            schema
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SchemaError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let parsed: Result<Self, String> = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&text).map_err(|e| e.to_string()),
+            _ => serde_json::from_str(&text).map_err(|e| e.to_string()),
+        };
+        parsed
+            .and_then(|schema| schema.validate().map(|_| schema))
+            .map_err(|e| SchemaError::Invalid(path.display().to_string(), e))
+    }
+
+    /// Checks that no two fields would be written under the same name, so documents can be read back
+    fn validate(&self) -> Result<(), String> {
+        for (number, fields) in &self.put2d {
+            if fields.contains_key(&TYPE_POSITION) {
+                return Err(format!(
+                    "the type number of Put2D type {number} can't be named"
+                ));
+            }
+            let mut names = HashSet::new();
+            for (position, info) in fields {
+                let name = info.name.as_str();
+                if !names.insert(name) {
+                    return Err(format!("Put2D type {number} names two fields `{name}`"));
+                }
+                let shadows = if *position == LEADING_POSITION {
+                    ENTRY_KEYS.contains(&name)
+                } else {
+                    put2d_position(number, name).is_some_and(|p| p != *position)
+                };
+                if shadows {
+                    return Err(format!(
+                        "`{name}` is already the name of another field of Put2D type {number}"
+                    ));
+                }
+            }
+        }
+        let mut names = HashSet::new();
+        for (column, info) in &self.stats {
+            let name = info.name.as_str();
+            if !names.insert(name) {
+                return Err(format!("two stats fields are named `{name}`"));
+            }
+            if StatsEntry::FIELDS
+                .iter()
+                .position(|f| *f == name)
+                .is_some_and(|c| c != *column)
+            {
+                return Err(format!(
+                    "`{name}` is already the name of another stats field"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn put2d_field(&self, type_number: &str, position: usize) -> Option<&FieldInfo> {
+        self.put2d.get(type_number)?.get(&position)
+    }
+
+    /// The schema's name for a Put2D field, or else the parser's
+    pub fn put2d_field_name(&self, type_number: &str, position: usize) -> Cow<'_, str> {
+        match self.put2d_field(type_number, position) {
+            Some(info) => Cow::Borrowed(&info.name),
+            None => put2d_default_name(type_number, position),
+        }
+    }
+
+    /// The schema's name for a stats column, or else the parser's
+    pub fn stats_field_name(&self, column: usize) -> Cow<'_, str> {
+        match self.stats.get(&column) {
+            Some(info) => Cow::Borrowed(&info.name),
+            None => Cow::Borrowed(StatsEntry::FIELDS.get(column).copied().unwrap_or("extra")),
+        }
+    }
+
+    /// Finds a stats column by the schema's name for it or the parser's
+    pub fn stats_column(&self, name: &str) -> Option<usize> {
+        self.stats
+            .iter()
+            .find(|(_, info)| info.name == name)
+            .map(|(column, _)| *column)
+            .or_else(|| StatsEntry::FIELDS.iter().position(|f| *f == name))
+    }
+
+    /// The raw value for a stats column, given either the value itself or the meaning the schema gives it
+    pub fn stats_value<'a>(&'a self, column: usize, value: &'a str) -> &'a str {
+        self.stats
+            .get(&column)
+            .and_then(|info| info.raw_value(value))
+            .unwrap_or(value)
+    }
+
+    /// Every field of a Put2D entry but its type number, named by the schema where it can and by the parser
+    /// otherwise. Fields of unknown entry types are named `field_<position>`.
+    pub fn put2d_fields(&self, entry: &Put2DEntry) -> Vec<NamedField> {
+        let number = entry.entry_type.number();
+        format!("{} {}", entry.unknown, entry.entry_type)
+            .split(' ')
+            .enumerate()
+            .filter(|(position, _)| *position != TYPE_POSITION)
+            .map(|(position, value)| {
+                let default = put2d_default_name(number, position).into_owned();
+                self.named(self.put2d_field(number, position), position, default, value)
+            })
+            .collect()
+    }
+
+    /// Every column of a raw stats line, named by the schema where it can and by the parser otherwise
+    pub fn stats_fields(&self, line: &str) -> Vec<NamedField> {
+        line.split(' ')
+            .enumerate()
+            .map(|(column, value)| {
+                let default = self.stats_field_name(column).into_owned();
+                self.named(self.stats.get(&column), column, default, value)
+            })
+            .collect()
+    }
+
+    fn named(
+        &self,
+        info: Option<&FieldInfo>,
+        position: usize,
+        default: String,
+        value: &str,
+    ) -> NamedField {
+        NamedField {
+            position,
+            name: info.map_or(default, |info| info.name.clone()),
+            value: value.to_string(),
+            unit: info.and_then(|info| info.unit.clone()),
+            meaning: info.and_then(|info| info.values.get(value).cloned()),
+        }
+    }
+
+    /// Renames the fields of a Put2D document's entries to the schema's names, and swaps values for their meanings.
+    /// The fields of entry types the parser doesn't understand are written as an object once the schema names any of
+    /// them.
+    pub fn export_put2d(&self, document: &mut Value) {
+        for (number, entry) in put2d_entries(document) {
+            let Some(fields) = self.put2d.get(&number) else {
+                continue;
+            };
+            rename(entry, &self.put2d_renames(&number, true, true), true);
+            match entry.get_mut("entry").and_then(Value::as_object_mut) {
+                Some(typed) => rename(typed, &self.put2d_renames(&number, false, true), true),
+                None => {
+                    let Some(Value::Array(tokens)) = entry.get_mut("fields") else {
+                        continue;
+                    };
+                    let object: Map<String, Value> = std::mem::take(tokens)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, token)| {
+                            let position = i + TYPE_POSITION + 1;
+                            let value = match fields.get(&position) {
+                                Some(info) => info.export_value(token),
+                                None => token,
+                            };
+                            (self.put2d_field_name(&number, position).into_owned(), value)
+                        })
+                        .collect();
+                    entry.insert("fields".to_string(), Value::Object(object));
+                }
+            }
+        }
+    }
+
+    /// Undoes [`FieldSchema::export_put2d`], so the document can be read as a [`Put2D`](super::put2d::Put2D)
+    pub fn import_put2d(&self, document: &mut Value) -> Result<(), SchemaError> {
+        for (number, entry) in put2d_entries(document) {
+            rename(entry, &self.put2d_renames(&number, true, false), false);
+            if let Some(typed) = entry.get_mut("entry").and_then(Value::as_object_mut) {
+                rename(typed, &self.put2d_renames(&number, false, false), false);
+                continue;
+            }
+            let Some(Value::Object(object)) = entry.get_mut("fields") else {
+                continue;
+            };
+            let fields = self.put2d.get(&number);
+            let mut tokens = BTreeMap::new();
+            for (name, value) in std::mem::take(object) {
+                let position = fields
+                    .and_then(|f| f.iter().find(|(_, info)| info.name == name))
+                    .map(|(position, _)| *position)
+                    .or_else(|| put2d_position(&number, &name))
+                    .filter(|position| *position > TYPE_POSITION)
+                    .ok_or_else(|| SchemaError::UnknownField(number.clone(), name.clone()))?;
+                let value = match fields.and_then(|f| f.get(&position)) {
+                    Some(info) => info.import_value(value),
+                    None => value,
+                };
+                // the fields of unknown entry types are always text
+                let token = match value {
+                    Value::String(token) => token,
+                    other => other.to_string(),
+                };
+                tokens.insert(position, Value::String(token));
+            }
+            // every field up to the last one must be there, since they're only told apart by position
+            let expected = (TYPE_POSITION + 1..)
+                .zip(tokens.keys())
+                .find(|(e, p)| e != *p);
+            if let Some((position, _)) = expected {
+                let name = self.put2d_field_name(&number, position).into_owned();
+                return Err(SchemaError::MissingField(number, name));
+            }
+            entry.insert(
+                "fields".to_string(),
+                Value::Array(tokens.into_values().collect()),
+            );
+        }
+        Ok(())
+    }
+
+    /// The keys the schema renames in a document's entries, from the parser's names to the schema's when exporting
+    /// and back when importing. Covers either the leading token or the fields following the type number.
+    fn put2d_renames(
+        &self,
+        type_number: &str,
+        leading: bool,
+        export: bool,
+    ) -> HashMap<String, (String, &FieldInfo)> {
+        let Some(fields) = self.put2d.get(type_number) else {
+            return HashMap::new();
+        };
+        fields
+            .iter()
+            .filter(|(position, _)| (**position == LEADING_POSITION) == leading)
+            .map(|(position, info)| {
+                let default = put2d_default_name(type_number, *position).into_owned();
+                match export {
+                    true => (default, (info.name.clone(), info)),
+                    false => (info.name.clone(), (default, info)),
+                }
+            })
+            .collect()
+    }
+}
+
+impl FieldInfo {
+    /// The meaning of a value, if the schema gives it one
+    fn export_value(&self, value: Value) -> Value {
+        let raw = match &value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        match self.values.get(&raw) {
+            Some(meaning) => Value::String(meaning.clone()),
+            None => value,
+        }
+    }
+
+    /// The value behind a meaning, written as a number where it is one
+    fn import_value(&self, value: Value) -> Value {
+        let Value::String(meaning) = &value else {
+            return value;
+        };
+        match self.raw_value(meaning) {
+            Some(raw) => raw
+                .parse()
+                .map_or_else(|_| Value::String(raw.to_string()), Value::Number),
+            None => value,
+        }
+    }
+
+    fn raw_value(&self, meaning: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(_, m)| *m == meaning)
+            .map(|(raw, _)| raw.as_str())
+    }
+}
+
+/// The name the parser gives a Put2D field
+fn put2d_default_name(type_number: &str, position: usize) -> Cow<'static, str> {
+    let names = Put2DEntryType::field_names(type_number).unwrap_or_default();
+    match position {
+        LEADING_POSITION => Cow::Borrowed("unknown"),
+        TYPE_POSITION => Cow::Borrowed("type"),
+        _ => match names.get(position - TYPE_POSITION - 1) {
+            Some(name) => Cow::Borrowed(name),
+            None => Cow::Owned(format!("field_{position}")),
+        },
+    }
+}
+
+/// The position of the field the parser gives a name, among the fields following the type number
+fn put2d_position(type_number: &str, name: &str) -> Option<usize> {
+    let names = Put2DEntryType::field_names(type_number).unwrap_or_default();
+    names
+        .iter()
+        .position(|n| *n == name)
+        .map(|i| i + TYPE_POSITION + 1)
+        .or_else(|| name.strip_prefix("field_")?.parse().ok())
+}
+
+/// The type number and object of every entry in a Put2D document
+fn put2d_entries(document: &mut Value) -> Vec<(String, &mut Map<String, Value>)> {
+    let Some(entries) = document.get_mut("entries").and_then(Value::as_array_mut) else {
+        return vec![];
+    };
+    entries
+        .iter_mut()
+        .filter_map(Value::as_object_mut)
+        .filter_map(|entry| {
+            let number = match entry.get("type").and_then(Value::as_str) {
+                Some("UNKNOWN") => entry.get("number").and_then(Value::as_str)?.to_string(),
+                Some(name) => name.strip_prefix("TYPE")?.to_string(),
+                None => return None,
+            };
+            Some((number, entry))
+        })
+        .collect()
+}
+
+/// Renames keys in place, keeping their order, and converts the values of renamed keys to or from their meanings.
+/// Anything unexpected is left for deserializing to complain about.
+fn rename(
+    object: &mut Map<String, Value>,
+    renames: &HashMap<String, (String, &FieldInfo)>,
+    export: bool,
+) {
+    if renames.is_empty() {
+        return;
+    }
+    *object = std::mem::take(object)
+        .into_iter()
+        .map(|(key, value)| match renames.get(&key) {
+            Some((to, info)) if export => (to.clone(), info.export_value(value)),
+            Some((to, info)) => (to.clone(), info.import_value(value)),
+            None => (key, value),
+        })
+        .collect();
+}
+
+impl fmt::Display for NamedField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.value)?;
+        if let Some(unit) = &self.unit {
+            write!(f, " {unit}")?;
+        }
+        if let Some(meaning) = &self.meaning {
+            write!(f, " ({meaning})")?;
+        }
+        Ok(())
+    }
+}
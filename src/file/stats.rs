@@ -1,6 +1,7 @@
 use num_derive::FromPrimitive;
 use std::str::FromStr;
 
+use super::{decode::Decode, schema::FieldSchema, FileType};
 use crate::errors::ValidationError;
 
 #[derive(Debug)]
@@ -20,21 +21,66 @@ impl Stats {
             } else {
                 let entry = StatsEntry::from_data(&line);
                 match entry {
-                    Ok(entry) => {
-                        entries.push(entry);
-                        line.clear();
-                    }
+                    Ok(entry) => entries.push(entry),
                     Err(e) => {
                         println!(
                             "error parsing entry at index {current_index}: \n{e}\nskipping..."
                         );
                     }
                 }
+                // a skipped entry mustn't run into the next one
+                line.clear();
             }
             current_index += 1;
         }
         Self { entries }
     }
+
+    /// Replaces a single field of a player's entry in raw stats data, leaving every other byte untouched. Fields can
+    /// be given by the name `schema` gives them as well as their name in [`StatsEntry::FIELDS`], and values by the
+    /// meaning `schema` gives them. A value the parser couldn't read in that column is rejected.
+    pub fn set_field(
+        data: &[u8],
+        player: &str,
+        field: &str,
+        value: &str,
+        schema: &FieldSchema,
+    ) -> Result<Vec<u8>, ValidationError> {
+        let column = schema
+            .stats_column(field)
+            .ok_or_else(|| ValidationError::MissingField(field.to_string()))?;
+        let value = schema.stats_value(column, value);
+        if value.is_empty() || value.contains([' ', '\n']) {
+            return Err(ValidationError::IncorrectFormat(field.to_string()));
+        }
+        let mut lines: Vec<Vec<u8>> = data.split(|b| *b == 0x0A).map(<[u8]>::to_vec).collect();
+        let line = lines
+            .iter_mut()
+            .find(|line| {
+                let columns: Vec<&[u8]> = line.split(|b| *b == 0x20).collect();
+                columns[0] == player.as_bytes() && columns.len() > column
+            })
+            .ok_or_else(|| ValidationError::MissingField(player.to_string()))?;
+        let mut columns: Vec<&[u8]> = line.split(|b| *b == 0x20).collect();
+        columns[column] = value.as_bytes();
+        let edited = columns.join(&0x20);
+        // the value has to suit the column, so an entry the parser could read still can be
+        if StatsEntry::from_data(line).is_ok() && StatsEntry::from_data(&edited).is_err() {
+            return Err(ValidationError::IncorrectFormat(field.to_string()));
+        }
+        *line = edited;
+        Ok(lines.join(&0x0A))
+    }
+}
+
+impl Decode for Stats {
+    fn accepts(file_type: &FileType) -> bool {
+        matches!(file_type, FileType::STATS)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ValidationError> {
+        Ok(Self::from_data(data))
+    }
 }
 
 #[derive(Debug)]
@@ -63,6 +109,31 @@ pub struct StatsEntry {
 }
 
 impl StatsEntry {
+    /// The name of each space separated column of an entry, in order
+    pub const FIELDS: [&'static str; 21] = [
+        "name",
+        "team",
+        "grades",
+        "height",
+        "weight",
+        "shoot",
+        "pass",
+        "dribble",
+        "power",
+        "speed",
+        "quickness",
+        "jump",
+        "stamina",
+        "unknown_1",
+        "unknown_2",
+        "price",
+        "unknown_3",
+        "unknown_4",
+        "unknown_5",
+        "unknown_6",
+        "unknown_6_len",
+    ];
+
     pub fn from_data(data: &[u8]) -> Result<Self, ValidationError> {
         let mut current_index: usize = 0;
         let mut stats_string: String = String::new();
@@ -85,6 +156,16 @@ impl StatsEntry {
         //if stats_vec.len() < 31 { // some entries have no max/min, resulting in errors with the current StatsEntry implementation. these are being defaulted to blank entries for the time being
         //    return Default::default()
         //}
+        if stats_vec.len() < Self::FIELDS.len() {
+            return Err(ValidationError::MissingField(
+                Self::FIELDS[stats_vec.len()].to_string(),
+            ));
+        }
+        fn field<T: FromStr>(stats_vec: &[String], column: usize) -> Result<T, ValidationError> {
+            stats_vec[column].parse().map_err(|_| {
+                ValidationError::IncorrectFormat(StatsEntry::FIELDS[column].to_string())
+            })
+        }
         let unknown_6_location: usize = stats_vec.len() - 2;
         stats_string = stats_vec[unknown_6_location].clone();
         unknown_6_index = 0;
@@ -92,18 +173,22 @@ impl StatsEntry {
             if stats_string.as_bytes()[unknown_6_index] != 0x2C {
                 unknown_6_string.push(stats_string.as_bytes()[unknown_6_index] as char);
             } else {
-                unknown_6_vec.push(unknown_6_string.parse().unwrap());
+                unknown_6_vec.push(
+                    unknown_6_string
+                        .parse()
+                        .map_err(|_| ValidationError::IncorrectFormat("unknown_6".to_string()))?,
+                );
                 unknown_6_string.clear();
             }
             unknown_6_index += 1;
         }
         Ok(Self {
             name: stats_vec[0].clone(),
-            team: Team::from_id(stats_vec[1].parse().unwrap())?,
+            team: Team::from_id(field(&stats_vec, 1)?)?,
             // TODO: implement handling for this
             grades: stats_vec[2].parse()?,
-            height: stats_vec[3].parse().unwrap(),
-            weight: stats_vec[4].parse().unwrap(),
+            height: field(&stats_vec, 3)?,
+            weight: field(&stats_vec, 4)?,
             shoot: stats_vec[5].parse()?,
             pass: stats_vec[6].parse()?,
             dribble: stats_vec[7].parse()?,
@@ -112,14 +197,14 @@ impl StatsEntry {
             quickness: stats_vec[10].parse()?,
             jump: stats_vec[11].parse()?,
             stamina: stats_vec[12].parse()?,
-            unknown_1: stats_vec[13].parse().unwrap(),
-            unknown_2: stats_vec[14].parse().unwrap(),
-            price: stats_vec[15].parse().unwrap(),
-            unknown_3: stats_vec[16].parse().unwrap(),
-            unknown_4: stats_vec[17].parse().unwrap(),
-            unknown_5: stats_vec[18].parse().unwrap(),
+            unknown_1: field(&stats_vec, 13)?,
+            unknown_2: field(&stats_vec, 14)?,
+            price: field(&stats_vec, 15)?,
+            unknown_3: field(&stats_vec, 16)?,
+            unknown_4: field(&stats_vec, 17)?,
+            unknown_5: field(&stats_vec, 18)?,
             unknown_6: unknown_6_vec.clone(),
-            unknown_6_len: stats_vec[20].parse().unwrap(),
+            unknown_6_len: field(&stats_vec, 20)?,
         })
     }
 }
@@ -133,7 +218,11 @@ pub struct SkillRange {
 impl FromStr for SkillRange {
     type Err = ValidationError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let range: Vec<u8> = s.split('-').map(|s| s.parse::<u8>().unwrap()).collect();
+        let range: Vec<u8> = s
+            .split('-')
+            .map(|s| s.parse::<u8>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| ValidationError::IncorrectFormat("skill range".to_string()))?;
 
         if range.len() > 2 || range.is_empty() {
             return Err(ValidationError::IncorrectFormat("skill range".to_string()));
@@ -157,7 +246,11 @@ pub struct PositionGrades {
 impl FromStr for PositionGrades {
     type Err = ValidationError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let grades: Vec<u8> = s.split('-').map(|s| s.parse::<u8>().unwrap()).collect();
+        let grades: Vec<u8> = s
+            .split('-')
+            .map(|s| s.parse::<u8>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| ValidationError::IncorrectFormat("position grades".to_string()))?;
 
         if grades.len() != 5 {
             return Err(ValidationError::IncorrectFormat(
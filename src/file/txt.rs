@@ -0,0 +1,117 @@
+use encoding_rs::SHIFT_JIS;
+
+use super::{decode::Decode, FileType};
+use crate::errors::ValidationError;
+
+/// A plain Shift-JIS text resource, such as the text referenced by a Put2D script's `txt_path`
+#[derive(Debug, PartialEq)]
+pub struct Txt {
+    /// The first line, if it's a script style identifier (such as `put2d-script`) rather than text
+    pub header: Option<String>,
+    /// Every line after the header, without line endings
+    pub lines: Vec<String>,
+    /// Sequences within the lines that look like game control codes rather than text
+    pub control_codes: Vec<ControlCode>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ControlCode {
+    /// The index into [`Txt::lines`]
+    pub line: usize,
+    /// The character (not byte) offset within the line
+    pub column: usize,
+    pub code: String,
+}
+
+impl Txt {
+    pub fn from_data(data: &[u8]) -> Result<Self, ValidationError> {
+        let (res, _, errors) = SHIFT_JIS.decode(trim_padding(data));
+        if errors {
+            return Err(ValidationError::IncorrectFormat(
+                "Invalid SHIFT_JIS encoding!".to_string(),
+            ));
+        }
+        let mut lines: Vec<String> = res.lines().map(str::to_string).collect();
+        let header = if lines.first().is_some_and(|l| is_header(l)) {
+            Some(lines.remove(0))
+        } else {
+            None
+        };
+        let control_codes = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(i, line)| find_control_codes(i, line))
+            .collect();
+        Ok(Self {
+            header,
+            lines,
+            control_codes,
+        })
+    }
+}
+
+impl Decode for Txt {
+    fn accepts(file_type: &FileType) -> bool {
+        matches!(file_type, FileType::TXT)
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, ValidationError> {
+        Self::from_data(data)
+    }
+}
+
+/// Strips the zero padding that follows files within an InnerDAT
+pub(crate) fn trim_padding(data: &[u8]) -> &[u8] {
+    let end = data.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    &data[..end]
+}
+
+/// Identifiers like `put2d-script` or `font-type`: lowercase ASCII words joined by dashes
+pub(crate) fn is_header(line: &str) -> bool {
+    line.contains('-')
+        && !line.starts_with('-')
+        && line
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Finds raw control characters, backslash escapes (`\n`, `\c3`) and printf style placeholders (`%d`, `%02d`)
+fn find_control_codes(line_index: usize, line: &str) -> Vec<ControlCode> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut codes = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        match chars[i] {
+            c if c.is_ascii_control() && c != '\t' => i += 1,
+            '\\' if i + 1 < chars.len() && chars[i + 1].is_ascii_alphanumeric() => {
+                i += 2;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            '%' if i + 1 < chars.len() => {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                if i < chars.len() && "dsxXcu".contains(chars[i]) {
+                    i += 1;
+                } else {
+                    i = start + 1;
+                    continue;
+                }
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        codes.push(ControlCode {
+            line: line_index,
+            column: start,
+            code: chars[start..i].iter().collect(),
+        });
+    }
+    codes
+}
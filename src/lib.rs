@@ -1,36 +1,72 @@
+pub mod carve;
+pub mod convert;
+pub mod delta;
 pub mod errors;
 pub mod file;
+pub mod manifest;
+pub mod modpack;
 extern crate encoding_rs;
 extern crate num_derive;
 
 pub mod dat {
+    pub mod character;
+    pub mod classify;
+    pub mod diff;
+    pub mod duplicates;
+    pub mod naming;
+    pub mod patch;
+    pub mod references;
+    pub mod session;
+    pub mod slack;
+    pub mod tree;
+    pub mod ui;
+
     use std::{
         fs::File as ioFile,
         io::{Error, Read, Seek},
     };
 
-    use crate::file::{File, FileType};
+    use serde::Deserialize;
+
+    use crate::file::{detect::DetectorRegistry, File, FileType};
     pub use crate::{
         errors,
         file::{put2d, stats},
     };
+    use character::CharacterArchive;
+    use classify::ArchiveClassifier;
+    use ui::UiArchive;
 
     pub struct DAT {
         /// The file that the DAT originates from
         file: ioFile,
         /// The inner DAT files of the main DAT
         pub inner_dats: Vec<InnerDAT>,
+        /// Classifies files as they're indexed
+        detectors: DetectorRegistry,
     }
 
     impl DAT {
-        pub fn from_file(mut file: ioFile) -> Result<Self, Error> {
+        pub fn from_file(file: ioFile) -> Result<Self, Error> {
+            Self::from_file_with_detectors(file, DetectorRegistry::default())
+        }
+
+        /// Reads a DAT, classifying its files with a custom set of detectors
+        pub fn from_file_with_detectors(
+            mut file: ioFile,
+            detectors: DetectorRegistry,
+        ) -> Result<Self, Error> {
             let inner_dats: Vec<InnerDAT> = vec![];
             let mut buf: [u8; 4] = [0; 4];
             file.read_exact(&mut buf)?;
             let entry_count = u32::from_le_bytes(buf);
             let mut buf: [u8; 12] = [255; 12];
             let mut i = 0;
-            let mut dat = Self { file, inner_dats };
+            let mut dat = Self {
+                file,
+                inner_dats,
+                detectors,
+            };
             while i < entry_count {
                 // this throws an error if even one table is messed up, we could be more lenient with something like that
                 dat.file.read_exact(&mut buf)?;
@@ -43,6 +79,11 @@ pub mod dat {
             Ok(dat)
         }
 
+        /// The detectors used to classify this DAT's files
+        pub fn detectors(&self) -> &DetectorRegistry {
+            &self.detectors
+        }
+
         fn index_files(&self, inner_dat: &InnerDAT) -> Result<Vec<File>, Error> {
             let offset = inner_dat.offset;
             let mut file = &self.file;
@@ -56,7 +97,7 @@ pub mod dat {
             while i < entry_count {
                 file.read_exact(&mut buffer)?;
                 let head = file.stream_position().unwrap();
-                files.push(File::new(self, inner_dat, buffer, &files));
+                files.push(File::new(self, inner_dat, buffer));
                 file.seek(std::io::SeekFrom::Start(head))?;
                 i += 1;
             }
@@ -100,88 +141,85 @@ pub mod dat {
             };
             inner_dat.files = dat_file.index_files(&inner_dat).unwrap();
             inner_dat.archive_type = ArchiveType::from_archive(&inner_dat);
-            inner_dat.archive_name = inner_dat.get_names();
-            // Todo: Add enum to describe archive type (put2d, character, object, etc)
-            // Also add name handling
+            inner_dat.retype_files(inner_dat.file_types(dat_file));
+            inner_dat.name_files(inner_dat.file_names(dat_file));
+            inner_dat.archive_name = inner_dat.get_names(dat_file);
             inner_dat
         }
 
-        fn get_names(&mut self) -> Option<String> {
+        /// Types that files detection couldn't be sure of get from their place in a typed archive
+        fn file_types(&self, dat_file: &DAT) -> Vec<(usize, FileType)> {
+            match self.archive_type {
+                ArchiveType::UI => self
+                    .text_resources(dat_file)
+                    .into_iter()
+                    .map(|i| (i, FileType::TXT))
+                    .collect(),
+                _ => vec![],
+            }
+        }
+
+        fn retype_files(&mut self, file_types: Vec<(usize, FileType)>) {
+            for (i, file_type) in file_types {
+                self.files[i].file_type = file_type;
+            }
+        }
+
+        /// Names that files get from their place in a typed archive
+        fn file_names(&self, dat_file: &DAT) -> Vec<(usize, String)> {
             match self.archive_type {
-                ArchiveType::CHARACTER => {
-                    let file = &self.files[6];
-                    if let FileType::NAME { name } = &file.file_type {
-                        Some(
-                            name.file_path
-                                .split('/')
-                                .nth(4)
-                                .unwrap()
-                                .strip_suffix(".name.out")
-                                .unwrap()
-                                .to_string(),
-                        )
-                    } else {
-                        None
-                    }
-                }
-                ArchiveType::UI => {
-                    let file = &self.files.first().unwrap();
-                    if let FileType::PUT2D { put2d_script } = &file.file_type {
-                        Some(
-                            put2d_script
-                                .txd_path
-                                .clone()
-                                .split('/')
-                                .last()
-                                .unwrap()
-                                .strip_suffix(".txd")
-                                .unwrap()
-                                .to_string(),
-                        )
-                    } else {
-                        None
-                    }
-                }
+                ArchiveType::CHARACTER => CharacterArchive::new(dat_file, self)
+                    .map(|character| character.file_names())
+                    .unwrap_or_default(),
+                ArchiveType::UI => UiArchive::new(dat_file, self)
+                    .map(|ui| ui.file_names(dat_file))
+                    .unwrap_or_default(),
+                _ => vec![],
+            }
+        }
+
+        fn name_files(&mut self, names: Vec<(usize, String)>) {
+            for (i, name) in names {
+                self.files[i].file_name = Some(name);
+            }
+        }
+
+        fn get_names(&self, dat_file: &DAT) -> Option<String> {
+            match self.archive_type {
+                ArchiveType::CHARACTER => CharacterArchive::new(dat_file, self)
+                    .ok()?
+                    .character_name()
+                    .map(str::to_string),
+                ArchiveType::UI => UiArchive::new(dat_file, self).ok()?.name(),
                 _ => None,
             }
         }
     }
 
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
     pub enum ArchiveType {
         CHARACTER,
         OBJECT,
         UI,
+        /// Led by a map info file
+        MAP,
+        /// Font type information and its textures
+        FONT,
+        /// A player roster
+        STATS,
+        /// A bundle of MIF data scripts
+        SCRIPT,
+        /// Only textures and images
+        TEXTURE,
+        /// Only animations
+        ANIMATION,
         UNKNOWN,
     }
 
     impl ArchiveType {
+        /// Classifies an archive with the builtin [`ArchiveClassifier`] rules
         pub fn from_archive(inner: &InnerDAT) -> Self {
-            match inner.files.first().unwrap().file_type {
-                FileType::DFF => {
-                    if inner.entry_count > 7
-                        && matches!(inner.files[6].file_type, FileType::NAME { .. })
-                    {
-                        ArchiveType::CHARACTER
-                    } else if inner.entry_count >= 2
-                        && matches!(inner.files[1].file_type, FileType::TXD)
-                    {
-                        ArchiveType::OBJECT
-                    } else {
-                        ArchiveType::UNKNOWN
-                    }
-                }
-                FileType::PUT2D { .. } => ArchiveType::UI,
-                FileType::UNKNOWN => {
-                    if inner.entry_count > 7
-                        && matches!(inner.files[6].file_type, FileType::NAME { .. })
-                    {
-                        ArchiveType::CHARACTER
-                    } else {
-                        ArchiveType::UNKNOWN
-                    }
-                }
-                _ => ArchiveType::UNKNOWN,
-            }
+            ArchiveClassifier::builtin().classify(inner)
         }
     }
 }
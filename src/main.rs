@@ -1,20 +1,435 @@
 use std::{
-    fs::{create_dir, File},
+    collections::HashSet,
+    env,
+    fs::{create_dir, File, OpenOptions},
     path::Path,
+    process::exit,
 };
 
-use baskelian_toolbox::dat::DAT;
+use baskelian_toolbox::{
+    carve, convert,
+    dat::{classify::ArchiveClassifier, tree::PathOrigin, DAT},
+    delta,
+    errors::ArchiveError,
+    file::schema::FieldSchema,
+    manifest::Manifest,
+    modpack::{self, ModPackage},
+};
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("patch") => patch(&args[1..]),
+        Some("diff") => diff(&args[1..]),
+        Some("apply") => apply(&args[1..]),
+        Some("carve") => carve(&args[1..]),
+        Some("convert") => convert(&args[1..]),
+        Some("mod") => apply_mods(&args[1..]),
+        Some("explain") => explain(&args[1..]),
+        Some("manifest") => manifest(&args[1..]),
+        Some("verify") => verify(&args[1..]),
+        Some("dat") => match args.get(1).map(String::as_str) {
+            Some("archives") => dat_archives(&args[2..]),
+            Some("diff") => dat_diff(&args[2..]),
+            Some("duplicates") => dat_duplicates(&args[2..]),
+            Some("refs") => dat_refs(&args[2..]),
+            Some("slack") => dat_slack(&args[2..]),
+            Some("tree") => dat_tree(&args[2..]),
+            _ => usage("dat <archives|diff|duplicates|refs|slack|tree> ..."),
+        },
+        _ => extract(),
+    }
+}
+
+fn usage(message: &str) -> ! {
+    eprintln!("usage: {message}");
+    exit(2)
+}
+
+fn patch(args: &[String]) {
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+    let args: Vec<&String> = args.iter().filter(|a| *a != "--dry-run").collect();
+    if args.len() != 4 {
+        usage("patch <DAT> <inner index> <file index> <replacement> [--dry-run]");
+    }
+    let inner_index: usize = args[1].parse().expect("invalid inner index");
+    let file_index: usize = args[2].parse().expect("invalid file index");
+    let data = std::fs::read(args[3]).expect("replacement file not found");
+    let dat_file = OpenOptions::new()
+        .read(true)
+        .write(!dry_run)
+        .open(args[0])
+        .expect("DAT file not found");
+    let mut dat = DAT::from_file(dat_file).unwrap();
+    if dry_run {
+        let strategy = dat.plan_patch(inner_index, file_index, data.len()).unwrap();
+        println!("Would patch {inner_index}-{file_index} {strategy}");
+    } else {
+        let strategy = dat.patch_file(inner_index, file_index, &data).unwrap();
+        println!("Patched {inner_index}-{file_index} {strategy}");
+    }
+}
+
+fn diff(args: &[String]) {
+    if args.len() != 3 {
+        usage("diff <original> <modified> <patch>");
+    }
+    let mut source = File::open(&args[0]).expect("original file not found");
+    let mut target = File::open(&args[1]).expect("modified file not found");
+    let mut patch = File::create(&args[2]).expect("could not create patch file");
+    let summary = delta::diff(&mut source, &mut target, &mut patch).unwrap();
+    println!(
+        "Wrote patch {} ({} bytes copied from the original, {} bytes stored)",
+        args[2], summary.copied, summary.literal
+    );
+}
+
+fn apply(args: &[String]) {
+    if args.len() != 3 {
+        usage("apply <original> <patch> <output>");
+    }
+    let mut source = File::open(&args[0]).expect("original file not found");
+    let mut patch = File::open(&args[1]).expect("patch file not found");
+    let mut target = File::create(&args[2]).expect("could not create output file");
+    if let Err(e) = delta::apply(&mut source, &mut patch, &mut target) {
+        drop(target);
+        let _ = std::fs::remove_file(&args[2]);
+        eprintln!("Failed to apply patch: {e}");
+        exit(1);
+    }
+    println!("Wrote {}", args[2]);
+}
+
+fn carve(args: &[String]) {
+    if args.is_empty() || args.len() > 2 {
+        usage("carve <file> [output directory]");
+    }
+    let mut file = File::open(&args[0]).expect("file not found");
+    let carved = carve::carve(&mut file).unwrap();
+    for found in &carved {
+        println!("{found}");
+    }
+    println!("Found {} files", carved.len());
+    if let Some(output) = args.get(1) {
+        std::fs::create_dir_all(output).expect("could not create output directory");
+        for found in &carved {
+            let file_path =
+                Path::new(output).join(format!("{:08x}{}", found.offset, found.file_type));
+            std::fs::write(&file_path, carve::read_carved(&mut file, found).unwrap()).unwrap();
+        }
+        println!("Wrote {} files to {output}", carved.len());
+    }
+}
+
+fn convert(args: &[String]) {
+    if args.len() < 2 || args.len() > 3 {
+        usage("convert <input> <output> [schema] (.put2d to .json/.yaml, or back)");
+    }
+    let schema = load_schema(args.get(2));
+    if let Err(e) = convert::convert_with_schema(Path::new(&args[0]), Path::new(&args[1]), &schema)
+    {
+        eprintln!("Failed to convert {}: {e}", args[0]);
+        exit(1);
+    }
+    println!("Wrote {}", args[1]);
+}
+
+fn apply_mods(args: &[String]) {
+    let (args, schema) = match args.iter().position(|a| a == "--schema") {
+        Some(i) if i + 1 < args.len() => {
+            let mut rest = args.to_vec();
+            let schema = rest.drain(i..i + 2).nth(1);
+            (rest, schema)
+        }
+        _ => (args.to_vec(), None),
+    };
+    if args.len() < 3 {
+        usage("mod <DAT> <output> <mod directory>... [--schema file] (mods are applied in the order given)");
+    }
+    let schema = load_schema(schema.as_ref());
+    let dat = DAT::from_file(File::open(&args[0]).expect("DAT file not found")).unwrap();
+    let mods: Result<Vec<ModPackage>, _> = args[2..].iter().map(ModPackage::load).collect();
+    let mods = mods.unwrap_or_else(|e| {
+        eprintln!("Failed to load mod: {e}");
+        exit(1)
+    });
+    match modpack::find_stale_duplicates(&dat, &mods) {
+        Ok(stale) => {
+            for duplicate in &stale {
+                println!("Warning: {duplicate}");
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to check for duplicates: {e}");
+            exit(1);
+        }
+    }
+    match modpack::merge_with_schema(&dat, &mods, &args[1], &schema) {
+        Ok(conflicts) => {
+            for conflict in &conflicts {
+                println!("Conflict: {conflict}");
+            }
+            println!("Merged {} mods into {}", mods.len(), args[1]);
+        }
+        Err(e) => {
+            eprintln!("Failed to merge mods: {e}");
+            exit(1);
+        }
+    }
+}
+
+fn dat_diff(args: &[String]) {
+    if args.len() < 2 || args.len() > 3 {
+        usage("dat diff <old DAT> <new DAT> [schema]");
+    }
+    let old = DAT::from_file(File::open(&args[0]).expect("old DAT file not found")).unwrap();
+    let new = DAT::from_file(File::open(&args[1]).expect("new DAT file not found")).unwrap();
+    let schema = load_schema(args.get(2));
+    print!("{}", old.diff_with_schema(&new, &schema).unwrap());
+}
+
+fn load_schema(path: Option<&String>) -> FieldSchema {
+    match path {
+        Some(path) => FieldSchema::load(path).unwrap_or_else(|e| {
+            eprintln!("Failed to load schema: {e}");
+            exit(1)
+        }),
+        None => FieldSchema::builtin().clone(),
+    }
+}
+
+fn dat_slack(args: &[String]) {
+    if args.is_empty() || args.len() > 2 {
+        usage("dat slack <DAT> [output directory]");
+    }
+    let dat = DAT::from_file(File::open(&args[0]).expect("DAT file not found")).unwrap();
+    let report = dat.scan_slack().unwrap();
+    print!("{report}");
+    if let Some(output) = args.get(1) {
+        std::fs::create_dir_all(output).expect("could not create output directory");
+        for region in &report.slack {
+            let extension = region
+                .file_type()
+                .map(|t| t.to_string())
+                .unwrap_or_default();
+            let file_path =
+                Path::new(output).join(format!("{:08x}{extension}", region.range.start));
+            std::fs::write(&file_path, dat.read_slack(region).unwrap()).unwrap();
+            println!("Carved {}", file_path.display());
+        }
+    }
+}
+
+fn dat_archives(args: &[String]) {
+    if args.is_empty() || args.len() > 2 {
+        usage("dat archives <DAT> [rules]");
+    }
+    let mut dat = DAT::from_file(File::open(&args[0]).expect("DAT file not found")).unwrap();
+    let classifier = match args.get(1) {
+        Some(path) => ArchiveClassifier::load(path).unwrap_or_else(|e| {
+            eprintln!("Failed to load rules: {e}");
+            exit(1)
+        }),
+        None => ArchiveClassifier::default(),
+    };
+    dat.reclassify(&classifier);
+    dat.name_archives();
+    for (i, inner_dat) in dat.inner_dats.iter().enumerate() {
+        let rule = classifier
+            .rule_for(inner_dat)
+            .map_or("no rule matched", |r| r.description.as_str());
+        println!(
+            "{i} {:?} {} ({rule})",
+            inner_dat.archive_type,
+            inner_dat.archive_name.as_deref().unwrap_or("unnamed")
+        );
+        if let Ok(character) = dat.character(i) {
+            for mismatch in character.mismatches() {
+                println!("  Warning: {mismatch}");
+            }
+        }
+        match dat.ui(i) {
+            Ok(ui) => {
+                for missing in ui.missing(&dat) {
+                    println!("  Warning: {missing}");
+                }
+            }
+            Err(ArchiveError::WrongType(_)) => {}
+            Err(e) => println!("  Warning: the Put2D script can't be read: {e}"),
+        }
+    }
+}
+
+fn dat_duplicates(args: &[String]) {
+    if args.is_empty() || args.len() > 2 {
+        usage("dat duplicates <DAT> [output]");
+    }
+    let dat = DAT::from_file(File::open(&args[0]).expect("DAT file not found")).unwrap();
+    print!("{}", dat.find_duplicates().unwrap());
+    if let Some(output) = args.get(1) {
+        let mut session = dat.edit();
+        session.share_duplicates(true);
+        session.commit(output).unwrap();
+        println!("Wrote {output} with duplicates within each InnerDAT sharing storage");
+    }
+}
+
+fn dat_refs(args: &[String]) {
+    let (args, dot) = match args.iter().position(|a| a == "--dot") {
+        Some(i) if i + 1 < args.len() => {
+            let mut rest = args.to_vec();
+            let dot = rest.drain(i..i + 2).nth(1);
+            (rest, dot)
+        }
+        _ => (args.to_vec(), None),
+    };
+    if args.len() != 1 && args.len() != 3 {
+        usage("dat refs <DAT> [inner index] [file index] [--dot output]");
+    }
+    let dat = DAT::from_file(File::open(&args[0]).expect("DAT file not found")).unwrap();
+    let graph = dat.references();
+    if args.len() == 3 {
+        let inner_index: usize = args[1].parse().expect("invalid inner index");
+        let file_index: usize = args[2].parse().expect("invalid file index");
+        for reference in graph.references_from(inner_index, file_index) {
+            println!("{reference}");
+        }
+        for reference in graph.users_of(inner_index, file_index) {
+            println!("{reference}");
+        }
+        for unreadable in &graph.unreadable {
+            if unreadable.file == (inner_index, file_index) {
+                println!("{unreadable}");
+            }
+        }
+    } else {
+        let dangling: Vec<_> = graph.dangling().collect();
+        for reference in &dangling {
+            println!("{reference}");
+        }
+        for unreadable in &graph.unreadable {
+            println!("{unreadable}");
+        }
+        println!(
+            "{} references, {} dangling, {} unreadable files",
+            graph.references.len(),
+            dangling.len(),
+            graph.unreadable.len()
+        );
+    }
+    if let Some(dot) = dot {
+        std::fs::write(&dot, graph.to_dot(&dat)).unwrap();
+        println!("Wrote {dot}");
+    }
+}
+
+fn dat_tree(args: &[String]) {
+    if args.len() != 2 {
+        usage("dat tree <DAT> <output directory>");
+    }
+    let mut dat = DAT::from_file(File::open(&args[0]).expect("DAT file not found")).unwrap();
+    dat.name_archives();
+    let tree = dat.dev_tree();
+    for tree_file in &tree.files {
+        println!("{tree_file}");
+    }
+    tree.extract(&dat, &args[1]).unwrap();
+    println!(
+        "Wrote {} files to {} ({} at their dev path, {} beside them, {} by index)",
+        tree.files.len(),
+        args[1],
+        tree.count(PathOrigin::Embedded),
+        tree.count(PathOrigin::Sibling),
+        tree.count(PathOrigin::Index)
+    );
+}
+
+fn manifest(args: &[String]) {
+    if args.len() != 2 {
+        usage("manifest <DAT> <output>");
+    }
+    let dat = DAT::from_file(File::open(&args[0]).expect("DAT file not found")).unwrap();
+    Manifest::from_dat(&dat).unwrap().save(&args[1]).unwrap();
+    println!(
+        "Wrote hashes of {} InnerDATs to {}",
+        dat.inner_dats.len(),
+        args[1]
+    );
+}
+
+fn verify(args: &[String]) {
+    if args.len() != 2 {
+        usage("verify <DAT> <manifest>");
+    }
+    let dat = DAT::from_file(File::open(&args[0]).expect("DAT file not found")).unwrap();
+    let expected = Manifest::load(&args[1]).unwrap_or_else(|e| {
+        eprintln!("Failed to load manifest: {e}");
+        exit(1)
+    });
+    if !expected.has_hashes() {
+        eprintln!("The manifest has no hashes to verify against");
+        exit(1);
+    }
+    let mismatches = expected.verify(&dat).unwrap();
+    for mismatch in &mismatches {
+        println!("{mismatch}");
+    }
+    if mismatches.is_empty() {
+        println!("All files match");
+    } else {
+        println!("{} differences", mismatches.len());
+        exit(1);
+    }
+}
+
+fn explain(args: &[String]) {
+    if args.len() != 3 {
+        usage("explain <DAT> <inner index> <file index>");
+    }
+    let dat = DAT::from_file(File::open(&args[0]).expect("DAT file not found")).unwrap();
+    let inner_dat = dat
+        .inner_dats
+        .get(args[1].parse::<usize>().expect("invalid inner index"))
+        .expect("InnerDAT not found");
+    let file = inner_dat
+        .files
+        .get(args[2].parse::<usize>().expect("invalid file index"))
+        .expect("file not found");
+    let data = dat.read_file(inner_dat, file).unwrap();
+    let extension = match file.file_type.to_string() {
+        e if e.is_empty() => "unknown".to_string(),
+        e => e,
+    };
+    println!(
+        "{}-{} is {} bytes, typed as {extension}",
+        args[1],
+        args[2],
+        data.len()
+    );
+    print!("{}", dat.detectors().explain(&data));
+}
+
+fn extract() {
     let dat_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("_artifacts/DATA.DAT");
     let extract_err = create_dir(dat_path.parent().unwrap().join("extracted")).err();
-    if extract_err.is_some() {
-        if !Path::new(&dat_path.parent().unwrap().join("extracted")).exists() {
-            panic!("unknown directory error")
-        }
+    if extract_err.is_some() && !Path::new(&dat_path.parent().unwrap().join("extracted")).exists() {
+        panic!("unknown directory error")
     }
     let dat_file = File::open(&dat_path).expect("DATA.DAT file not found in _artifacts folder!");
-    let dat = DAT::from_file(dat_file).unwrap();
+    let mut dat = DAT::from_file(dat_file).unwrap();
+    dat.name_archives();
+    // names.json beside the DAT overrides the names the toolbox finds on its own
+    let names_path = dat_path.with_file_name("names.json");
+    if names_path.exists() {
+        dat.apply_names(&Manifest::load(&names_path).expect("invalid names.json"));
+    }
+    let mut used_names = HashSet::new();
     for (i, inner_dat) in dat.inner_dats.as_slice().iter().enumerate() {
+        // archives can end up with the same name, such as when they hold the same textures
+        let archive_name = match &inner_dat.archive_name {
+            Some(name) if used_names.insert(name.clone()) => name.clone(),
+            Some(name) => format!("{name}_{i}"),
+            None => i.to_string(),
+        };
         let mut all_same_name = false;
         if inner_dat
             .files
@@ -27,22 +442,21 @@ fn main() {
             let data = dat.read_file(inner_dat, file).unwrap();
             let mut file_path = Path::new(&dat_path).parent().unwrap().join(format!(
                 "extracted/{}-{}{}",
-                inner_dat.archive_name.clone().unwrap_or(i.to_string()),
+                archive_name,
                 file.file_name.clone().unwrap_or(j.to_string()),
                 file.file_type
             ));
 
             if all_same_name {
-                file_path = Path::new(&dat_path).parent().unwrap().join(format!(
-                    "extracted/{}{}",
-                    inner_dat.archive_name.clone().unwrap(),
-                    file.file_type
-                ));
+                file_path = Path::new(&dat_path)
+                    .parent()
+                    .unwrap()
+                    .join(format!("extracted/{}{}", archive_name, file.file_type));
             }
             std::fs::write(file_path.clone(), data).unwrap();
             println!(
                 "Finished file {}",
-                file_path.to_str().unwrap().split('\\').last().unwrap()
+                file_path.to_str().unwrap().split('\\').next_back().unwrap()
             );
         }
     }
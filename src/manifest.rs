@@ -0,0 +1,179 @@
+//! SHA-1 and CRC32 hashes of every file in a DAT.
+//!
+//! A manifest is JSON, listing each InnerDAT by index along with an optional human-readable name. Manifests are made
+//! from a copy of the DAT the user trusts, such as their own retail DATA.DAT, and [`Manifest::verify`] compares
+//! another copy against one. Names can be added by hand, so a manifest can also be used as a name table with
+//! [`DAT::apply_names`](crate::dat::DAT::apply_names).
+//!
+//! ```json
+//! {
+//!     "archives": [
+//!         {
+//!             "archive": 2,
+//!             "name": "empty_file_example",
+//!             "files": [
+//!                 { "size": 0, "crc32": "00000000", "sha1": "da39a3ee5e6b4b0d3255bfef95601890afd80709" }
+//!             ]
+//!         }
+//!     ]
+//! }
+//! ```
+
+use std::{fmt, fs, io::Error, path::Path};
+
+use serde::{Deserialize, Serialize};
+use sha1_smol::Sha1;
+
+use crate::{dat::DAT, errors::ManifestError};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub archives: Vec<ArchiveHashes>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveHashes {
+    /// The index of the InnerDAT within the DAT
+    pub archive: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// May be left empty to only name an archive
+    #[serde(default)]
+    pub files: Vec<FileHashes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileHashes {
+    pub size: u32,
+    /// Lowercase hex
+    pub crc32: String,
+    /// Lowercase hex
+    pub sha1: String,
+}
+
+/// A way a DAT differs from a manifest
+#[derive(Debug)]
+pub enum Mismatch {
+    MissingArchive(usize),
+    ExtraArchive(usize),
+    MissingFile(usize, usize),
+    ExtraFile(usize, usize),
+    Changed {
+        archive: usize,
+        file: usize,
+        expected: FileHashes,
+        actual: FileHashes,
+    },
+}
+
+impl FileHashes {
+    pub fn from_data(data: &[u8]) -> Self {
+        Self {
+            size: data.len() as u32,
+            crc32: format!("{:08x}", crc32fast::hash(data)),
+            sha1: Sha1::from(data).digest().to_string(),
+        }
+    }
+}
+
+impl Manifest {
+    /// Hashes every file in the DAT
+    pub fn from_dat(dat: &DAT) -> Result<Self, Error> {
+        let mut archives = vec![];
+        for (i, inner_dat) in dat.inner_dats.iter().enumerate() {
+            let mut files = vec![];
+            for file in &inner_dat.files {
+                files.push(FileHashes::from_data(&dat.read_file(inner_dat, file)?));
+            }
+            archives.push(ArchiveHashes {
+                archive: i,
+                name: inner_dat.archive_name.clone(),
+                files,
+            });
+        }
+        Ok(Self { archives })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        let path = path.as_ref();
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| ManifestError::Invalid(path.display().to_string(), e.to_string()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn archive(&self, archive: usize) -> Option<&ArchiveHashes> {
+        self.archives.iter().find(|a| a.archive == archive)
+    }
+
+    /// The name of an archive, as long as the archive has the number of files the manifest expects
+    pub fn archive_name(&self, archive: usize, file_count: usize) -> Option<&str> {
+        self.archive(archive)
+            .filter(|a| a.files.is_empty() || a.files.len() == file_count)
+            .and_then(|a| a.name.as_deref())
+    }
+
+    /// Whether the manifest has hashes to verify against, rather than only names
+    pub fn has_hashes(&self) -> bool {
+        self.archives.iter().any(|a| !a.files.is_empty())
+    }
+
+    /// Hashes every file in the DAT and lists where it differs from the manifest. Archives the manifest only names
+    /// are skipped.
+    pub fn verify(&self, dat: &DAT) -> Result<Vec<Mismatch>, Error> {
+        let actual = Self::from_dat(dat)?;
+        let mut mismatches = vec![];
+        for expected in self.archives.iter().filter(|a| !a.files.is_empty()) {
+            let Some(archive) = actual.archive(expected.archive) else {
+                mismatches.push(Mismatch::MissingArchive(expected.archive));
+                continue;
+            };
+            for (j, expected_file) in expected.files.iter().enumerate() {
+                match archive.files.get(j) {
+                    Some(actual_file) if actual_file == expected_file => {}
+                    Some(actual_file) => mismatches.push(Mismatch::Changed {
+                        archive: expected.archive,
+                        file: j,
+                        expected: expected_file.clone(),
+                        actual: actual_file.clone(),
+                    }),
+                    None => mismatches.push(Mismatch::MissingFile(expected.archive, j)),
+                }
+            }
+            for j in expected.files.len()..archive.files.len() {
+                mismatches.push(Mismatch::ExtraFile(expected.archive, j));
+            }
+        }
+        if self.has_hashes() {
+            for archive in &actual.archives {
+                if self.archive(archive.archive).is_none() {
+                    mismatches.push(Mismatch::ExtraArchive(archive.archive));
+                }
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingArchive(i) => write!(f, "InnerDAT {i} is missing"),
+            Self::ExtraArchive(i) => write!(f, "InnerDAT {i} is not in the manifest"),
+            Self::MissingFile(i, j) => write!(f, "{i}-{j} is missing"),
+            Self::ExtraFile(i, j) => write!(f, "{i}-{j} is not in the manifest"),
+            Self::Changed {
+                archive,
+                file,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{archive}-{file} differs: {} bytes, SHA-1 {} (expected {} bytes, SHA-1 {})",
+                actual.size, actual.sha1, expected.size, expected.sha1
+            ),
+        }
+    }
+}
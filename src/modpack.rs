@@ -0,0 +1,329 @@
+//! Mod packages: a `mod.json` manifest alongside the replacement files it lists.
+//!
+//! Files are addressed by their InnerDAT and file index, optionally pinned to the archive name, file name and
+//! type (as its extension) the toolbox gives them so a mod can't silently land on the wrong file. Stats files can
+//! also be edited a single field at a time, so several mods can change different players in the same file.
+//!
+//! ```json
+//! {
+//!     "name": "Taller Gerbils",
+//!     "files": [
+//!         { "archive": 12, "archive_name": "title", "file": 1, "file_type": ".txd", "path": "title.txd" }
+//!     ],
+//!     "stats": [
+//!         { "archive": 3, "file": 0, "player": "Bob", "field": "height", "value": "205" }
+//!     ]
+//! }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dat::{InnerDAT, DAT},
+    errors::ModError,
+    file::{schema::FieldSchema, stats::Stats, File},
+};
+
+const MANIFEST: &str = "mod.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModPackage {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub files: Vec<FileReplacement>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stats: Vec<StatsEdit>,
+    /// The directory the manifest was loaded from, which replacement paths are relative to
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+/// Replaces a whole file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileReplacement {
+    #[serde(flatten)]
+    pub target: FileTarget,
+    /// The replacement file, relative to the mod's directory
+    pub path: PathBuf,
+}
+
+/// Changes one field of one player in a stats file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsEdit {
+    #[serde(flatten)]
+    pub target: FileTarget,
+    pub player: String,
+    /// One of [`StatsEntry::FIELDS`](crate::file::stats::StatsEntry::FIELDS), or a name the [`FieldSchema`] the mods
+    /// are merged with gives a column
+    pub field: String,
+    /// The raw value, or a meaning the [`FieldSchema`] gives one
+    pub value: String,
+}
+
+/// Identifies a file within the DAT
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileTarget {
+    pub archive: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_name: Option<String>,
+    pub file: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    /// The file's extension, as given by [`FileType`](crate::file::FileType)'s `Display`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_type: Option<String>,
+}
+
+impl fmt::Display for FileTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.archive, self.file)
+    }
+}
+
+impl FileTarget {
+    /// Finds the targeted file, checking it against any identity the mod pinned
+    fn resolve<'a>(
+        &self,
+        dat: &'a DAT,
+        mod_name: &str,
+    ) -> Result<(&'a InnerDAT, &'a File), ModError> {
+        let mismatch = || ModError::TargetMismatch(mod_name.to_string(), self.to_string());
+        let inner_dat = dat.inner_dats.get(self.archive).ok_or_else(mismatch)?;
+        let file = inner_dat.files.get(self.file).ok_or_else(mismatch)?;
+        if self
+            .archive_name
+            .as_ref()
+            .is_some_and(|name| inner_dat.archive_name.as_ref() != Some(name))
+            || self
+                .file_name
+                .as_ref()
+                .is_some_and(|name| file.file_name.as_ref() != Some(name))
+            || self
+                .file_type
+                .as_ref()
+                .is_some_and(|ext| file.file_type.to_string() != *ext)
+        {
+            return Err(mismatch());
+        }
+        Ok((inner_dat, file))
+    }
+}
+
+impl ModPackage {
+    /// Loads the manifest from a mod's directory
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, ModError> {
+        let root = dir.as_ref().to_path_buf();
+        let manifest = fs::read_to_string(root.join(MANIFEST))?;
+        let mut package: Self = serde_json::from_str(&manifest)
+            .map_err(|e| ModError::InvalidManifest(root.display().to_string(), e.to_string()))?;
+        package.root = root;
+        Ok(package)
+    }
+
+    /// Writes the manifest into `dir`, where [`ModPackage::load`] expects it
+    pub fn save(&self, dir: impl AsRef<Path>) -> Result<(), io::Error> {
+        fs::write(
+            dir.as_ref().join(MANIFEST),
+            serde_json::to_string_pretty(self)?,
+        )
+    }
+}
+
+/// Two or more mods changing the same thing; the last one in load order wins
+#[derive(Debug)]
+pub struct Conflict {
+    /// The file (and stats field, if any) being fought over
+    pub target: String,
+    /// The conflicting mods, in load order
+    pub mods: Vec<String>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is changed by {} ({} wins)",
+            self.target,
+            self.mods.join(", "),
+            self.mods.last().unwrap()
+        )
+    }
+}
+
+/// Lists everything touched by more than one mod, in the order the mods are given
+pub fn find_conflicts(mods: &[ModPackage]) -> Vec<Conflict> {
+    // whole-file replacements conflict with anything else touching the file
+    let mut files: BTreeMap<(usize, usize), Vec<&str>> = BTreeMap::new();
+    let mut fields: BTreeMap<(usize, usize, &str, &str), Vec<&str>> = BTreeMap::new();
+    for package in mods {
+        for replacement in &package.files {
+            let key = (replacement.target.archive, replacement.target.file);
+            files.entry(key).or_default().push(&package.name);
+        }
+        for edit in &package.stats {
+            let key = (
+                edit.target.archive,
+                edit.target.file,
+                edit.player.as_str(),
+                edit.field.as_str(),
+            );
+            fields.entry(key).or_default().push(&package.name);
+        }
+    }
+
+    let mut conflicts = vec![];
+    for ((archive, file), names) in &files {
+        let mut names = names.clone();
+        names.extend(
+            fields
+                .range((*archive, *file, "", "")..)
+                .take_while(|((a, f, _, _), _)| (a, f) == (archive, file))
+                .flat_map(|(_, editors)| editors),
+        );
+        let names = in_load_order(mods, names);
+        if names.len() > 1 {
+            conflicts.push(Conflict {
+                target: format!("{archive}-{file}"),
+                mods: names,
+            });
+        }
+    }
+    for ((archive, file, player, field), names) in &fields {
+        let names = in_load_order(mods, names.clone());
+        if names.len() > 1 && !files.contains_key(&(*archive, *file)) {
+            conflicts.push(Conflict {
+                target: format!("{archive}-{file} {player} {field}"),
+                mods: names,
+            });
+        }
+    }
+    conflicts
+}
+
+/// A file edited by a mod whose original contents are duplicated in files no mod touches
+#[derive(Debug)]
+pub struct StaleDuplicate {
+    pub target: String,
+    pub mods: Vec<String>,
+    /// The InnerDAT and file index of each copy that still holds the original contents
+    pub duplicates: Vec<(usize, usize)>,
+}
+
+impl fmt::Display for StaleDuplicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let duplicates: Vec<String> = self
+            .duplicates
+            .iter()
+            .map(|(i, j)| format!("{i}-{j}"))
+            .collect();
+        write!(
+            f,
+            "{} is edited by {}, but its untouched copies at {} still hold the original contents",
+            self.target,
+            self.mods.join(", "),
+            duplicates.join(", ")
+        )
+    }
+}
+
+/// Finds files the mods edit that have identical copies elsewhere in the DAT which no mod edits, since the game
+/// may load the stale copy instead.
+pub fn find_stale_duplicates(
+    dat: &DAT,
+    mods: &[ModPackage],
+) -> Result<Vec<StaleDuplicate>, ModError> {
+    let mut edited: BTreeMap<(usize, usize), Vec<&str>> = BTreeMap::new();
+    for package in mods {
+        let targets = package.files.iter().map(|r| &r.target);
+        for target in targets.chain(package.stats.iter().map(|e| &e.target)) {
+            let names = edited.entry((target.archive, target.file)).or_default();
+            if !names.contains(&package.name.as_str()) {
+                names.push(&package.name);
+            }
+        }
+    }
+    let report = dat.find_duplicates()?;
+    let mut stale = vec![];
+    for ((archive, file), names) in &edited {
+        let duplicates: Vec<(usize, usize)> = report
+            .duplicates_of(*archive, *file)
+            .into_iter()
+            .filter(|f| !edited.contains_key(f))
+            .collect();
+        if !duplicates.is_empty() {
+            stale.push(StaleDuplicate {
+                target: format!("{archive}-{file}"),
+                mods: in_load_order(mods, names.clone()),
+                duplicates,
+            });
+        }
+    }
+    Ok(stale)
+}
+
+/// Applies every mod in load order and writes the merged DAT to `output`, returning the conflicts that were
+/// resolved by load order.
+///
+/// Stats edits are applied on top of any replacement of the same file, so a later mod can tweak a player in a
+/// stats file an earlier mod replaced. Their fields are named as the builtin [`FieldSchema`] names them.
+pub fn merge(
+    dat: &DAT,
+    mods: &[ModPackage],
+    output: impl AsRef<Path>,
+) -> Result<Vec<Conflict>, ModError> {
+    merge_with_schema(dat, mods, output, FieldSchema::builtin())
+}
+
+/// Merges mods whose stats edits name fields with a custom schema
+pub fn merge_with_schema(
+    dat: &DAT,
+    mods: &[ModPackage],
+    output: impl AsRef<Path>,
+    schema: &FieldSchema,
+) -> Result<Vec<Conflict>, ModError> {
+    let conflicts = find_conflicts(mods);
+    let mut contents: HashMap<(usize, usize), Vec<u8>> = HashMap::new();
+    for package in mods {
+        for replacement in &package.files {
+            replacement.target.resolve(dat, &package.name)?;
+            let data = fs::read(package.root.join(&replacement.path))?;
+            contents.insert((replacement.target.archive, replacement.target.file), data);
+        }
+        for edit in &package.stats {
+            let (inner_dat, file) = edit.target.resolve(dat, &package.name)?;
+            let key = (edit.target.archive, edit.target.file);
+            let data = match contents.remove(&key) {
+                Some(data) => data,
+                None => dat.read_file(inner_dat, file)?,
+            };
+            let data = Stats::set_field(&data, &edit.player, &edit.field, &edit.value, schema)
+                .map_err(|e| {
+                    ModError::InvalidEdit(package.name.clone(), edit.target.to_string(), e)
+                })?;
+            contents.insert(key, data);
+        }
+    }
+
+    let mut session = dat.edit();
+    for ((archive, file), data) in contents {
+        session.replace(archive, file, data)?;
+    }
+    session.commit(output)?;
+    Ok(conflicts)
+}
+
+/// Deduplicates mod names, ordering them as they appear in `mods`
+fn in_load_order(mods: &[ModPackage], names: Vec<&str>) -> Vec<String> {
+    mods.iter()
+        .filter(|package| names.contains(&package.name.as_str()))
+        .map(|package| package.name.clone())
+        .collect()
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use std::io::Cursor;
+
+use baskelian_toolbox::{carve::carve, file::FileType};
+
+#[test]
+fn header_in_padding_does_not_swallow_what_follows() {
+    let clump = common::rw(
+        0x10,
+        &[common::rw(1, &[0; 12]), common::rw(0x0E, &[])].concat(),
+    );
+    // a clump header claiming 4 KiB, opening with an empty struct chunk and then nothing but zeros
+    let mut fake = common::rw(0x10, &common::rw(1, &[]));
+    fake[4..8].copy_from_slice(&0x1000u32.to_le_bytes());
+    let mut stream = clump.clone();
+    stream.resize(64, 0);
+    stream.extend(&fake);
+    stream.resize(128, 0);
+    stream.extend(common::txd(&["ball"]));
+    stream.resize(0x2000, 0);
+
+    let carved = carve(&mut Cursor::new(stream)).unwrap();
+    let found: Vec<(u64, u64, FileType)> = carved
+        .iter()
+        .map(|c| (c.offset, c.size, c.file_type.clone()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (0, clump.len() as u64, FileType::DFF),
+            (128, common::txd(&["ball"]).len() as u64, FileType::TXD),
+        ]
+    );
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use baskelian_toolbox::dat::character::CharacterMismatch;
+
+const NAME: &[u8] =
+    b"mif-name-data-script\nC:/dev/data/chr/bob.name.out\n4\nrun\njump\nfall\nland\n";
+
+#[test]
+fn animations_are_named_by_position() {
+    let dff = common::rw(0x10, &[2; 60]);
+    let txd = common::txd(&["bob"]);
+    let anm = common::rw(0x1B, &[3; 20]);
+    let dma = common::rw(0x1E, &[4; 20]);
+    let dat = common::open(&common::dat(&[common::inner_dat(&[
+        &dff, &txd, &txd, &txd, &txd, &txd, NAME, &anm, &dma, &txd, &anm,
+    ])]));
+    let character = dat.character(0).unwrap();
+    assert_eq!(character.character_name(), Some("bob"));
+    let names: Vec<&str> = character
+        .animations()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(names, ["run", "jump", "land"]);
+    assert!(std::ptr::eq(
+        character.animation("land").unwrap(),
+        &dat.inner_dats[0].files[10]
+    ));
+    assert_eq!(
+        dat.inner_dats[0].files[9].file_name.as_deref(),
+        Some("fall")
+    );
+    assert_eq!(
+        character.mismatches(),
+        [CharacterMismatch::NotAnimation {
+            file: 9,
+            file_type: ".txd".to_string()
+        }]
+    );
+}
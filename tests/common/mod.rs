@@ -0,0 +1,89 @@
+//! Builds small synthetic DATs for the tests to read
+#![allow(dead_code)]
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use baskelian_toolbox::dat::DAT;
+
+/// Files within an InnerDAT are padded to 16 bytes, InnerDATs to 2048
+fn pad(data: &mut Vec<u8>, alignment: usize) {
+    data.resize(data.len().div_ceil(alignment) * alignment, 0);
+}
+
+pub fn inner_dat(files: &[&[u8]]) -> Vec<u8> {
+    let mut table = (files.len() as u32).to_le_bytes().to_vec();
+    let mut offset = (4 + 8 * files.len()).div_ceil(16) * 16;
+    let mut body = vec![];
+    for file in files {
+        table.extend((offset as u32).to_le_bytes());
+        table.extend((file.len() as u32).to_le_bytes());
+        body.extend_from_slice(file);
+        pad(&mut body, 16);
+        offset = (4 + 8 * files.len()).div_ceil(16) * 16 + body.len();
+    }
+    pad(&mut table, 16);
+    table.extend(body);
+    table
+}
+
+pub fn dat(inner_dats: &[Vec<u8>]) -> Vec<u8> {
+    let mut table = (inner_dats.len() as u32).to_le_bytes().to_vec();
+    let mut offset = (4 + 12 * inner_dats.len()).div_ceil(2048) * 2048;
+    for inner_dat in inner_dats {
+        table.extend((offset as u32).to_le_bytes());
+        table.extend((inner_dat.len() as u32).to_le_bytes());
+        table.extend_from_slice(&inner_dat[0..4]);
+        offset += inner_dat.len().div_ceil(2048) * 2048;
+    }
+    pad(&mut table, 2048);
+    for inner_dat in inner_dats {
+        table.extend_from_slice(inner_dat);
+        pad(&mut table, 2048);
+    }
+    table
+}
+
+/// A RenderWare chunk
+pub fn rw(id: u32, body: &[u8]) -> Vec<u8> {
+    let mut chunk = id.to_le_bytes().to_vec();
+    chunk.extend((body.len() as u32).to_le_bytes());
+    chunk.extend(0x1803FFFFu32.to_le_bytes());
+    chunk.extend_from_slice(body);
+    chunk
+}
+
+/// A texture dictionary holding textures with the given names
+pub fn txd(textures: &[&str]) -> Vec<u8> {
+    let mut body = rw(1, &[0; 4]);
+    for texture in textures {
+        let mut name = texture.as_bytes().to_vec();
+        name.push(0);
+        pad(&mut name, 4);
+        let mut native = rw(1, b"PS2\0");
+        native.extend(rw(2, &name));
+        native.extend(rw(2, &[0; 4]));
+        body.extend(rw(0x15, &native));
+    }
+    rw(0x16, &body)
+}
+
+/// A unique path in the temp directory, which the caller should remove
+pub fn temp_path(name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let counter = COUNTER.fetch_add(1, Ordering::Relaxed);
+    env::temp_dir().join(format!("baskelian-{}-{counter}-{name}", process::id()))
+}
+
+/// Writes the DAT out and reads it back in
+pub fn open(data: &[u8]) -> DAT {
+    let path = temp_path("test.DAT");
+    fs::write(&path, data).unwrap();
+    let dat = DAT::from_file(fs::File::open(&path).unwrap()).unwrap();
+    fs::remove_file(path).unwrap();
+    dat
+}
@@ -0,0 +1,65 @@
+use std::io::Cursor;
+
+use baskelian_toolbox::delta::{apply, diff};
+
+/// Bytes that don't repeat, so every block of the source is distinct
+fn noise(len: usize, seed: u32) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        })
+        .collect()
+}
+
+#[test]
+fn patch_rebuilds_the_target() {
+    let source = noise(0x8000, 1);
+    let mut target = source.clone();
+    // an edit inside a block, an insertion that shifts everything after it, and new data at the end
+    target[0x1234] ^= 0xFF;
+    target.splice(0x3001..0x3001, noise(100, 2));
+    target.extend(noise(0x900, 3));
+
+    let mut patch = vec![];
+    let summary = diff(
+        &mut Cursor::new(&source),
+        &mut Cursor::new(&target),
+        &mut patch,
+    )
+    .unwrap();
+    assert_eq!(summary.target_size, target.len() as u64);
+    assert!(summary.copied >= 0x5000);
+    assert!(patch.len() < target.len() / 2);
+
+    let mut rebuilt = vec![];
+    apply(
+        &mut Cursor::new(&source),
+        &mut Cursor::new(&patch),
+        &mut rebuilt,
+    )
+    .unwrap();
+    assert_eq!(rebuilt, target);
+}
+
+#[test]
+fn patch_refuses_a_different_source() {
+    let source = noise(0x2000, 1);
+    let target = noise(0x2000, 2);
+    let mut patch = vec![];
+    diff(
+        &mut Cursor::new(&source),
+        &mut Cursor::new(&target),
+        &mut patch,
+    )
+    .unwrap();
+    let mut rebuilt = vec![];
+    assert!(apply(
+        &mut Cursor::new(&target),
+        &mut Cursor::new(&patch),
+        &mut rebuilt
+    )
+    .is_err());
+    assert!(rebuilt.is_empty());
+}
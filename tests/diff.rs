@@ -0,0 +1,25 @@
+mod common;
+
+use baskelian_toolbox::dat::diff::ArchiveChange;
+
+fn put2d_dat(id: &str, x: &str) -> Vec<u8> {
+    let put2d = format!(
+        "put2d-script\n{id}\n0\nC:/dev/ui/title.txd\nC:/dev/ui/title.txt\n1\n0 0 1 2 hello {x} 20 1 2 3 4 5 6\n"
+    );
+    common::dat(&[common::inner_dat(&[put2d.as_bytes()])])
+}
+
+#[test]
+fn values_written_differently_are_detailed() {
+    let old = common::open(&put2d_dat("007", "10"));
+    let new = common::open(&put2d_dat("7", "010"));
+    let diff = old.diff(&new).unwrap();
+    let ArchiveChange::Modified(files) = &diff.archives[0].change else {
+        panic!("the archive should be modified");
+    };
+    let details: Vec<String> = files[0].details.iter().map(|d| d.to_string()).collect();
+    assert_eq!(details.len(), 2);
+    assert_eq!(details[0], "id: 007 -> 7");
+    assert!(details[1].starts_with("entry 0."));
+    assert!(details[1].ends_with(": 10 -> 010"));
+}
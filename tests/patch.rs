@@ -0,0 +1,77 @@
+mod common;
+
+use std::fs::{self, OpenOptions};
+
+use baskelian_toolbox::dat::{patch::PatchStrategy, DAT};
+
+fn contents(dat: &DAT, inner_index: usize) -> Vec<Vec<u8>> {
+    let inner_dat = &dat.inner_dats[inner_index];
+    inner_dat
+        .files
+        .iter()
+        .map(|f| f.read_file(dat, inner_dat).unwrap())
+        .collect()
+}
+
+fn open_rw(path: &std::path::Path) -> DAT {
+    DAT::from_file(
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .unwrap(),
+    )
+    .unwrap()
+}
+
+fn sample() -> Vec<u8> {
+    common::dat(&[
+        common::inner_dat(&[&common::rw(0x10, &[1; 40]), &common::txd(&["a"]), &[7; 30]]),
+        common::inner_dat(&[&common::txd(&["b"])]),
+    ])
+}
+
+#[test]
+fn replacement_that_fits_is_written_in_place() {
+    let path = common::temp_path("in-place.DAT");
+    fs::write(&path, sample()).unwrap();
+    let mut dat = open_rw(&path);
+    let before = (contents(&dat, 0), contents(&dat, 1));
+
+    // the last file can grow into the padding after its InnerDAT
+    let replacement = vec![9; 64];
+    let strategy = dat.patch_file(0, 2, &replacement).unwrap();
+    assert!(matches!(strategy, PatchStrategy::InPlace { capacity } if capacity >= 64));
+    let len = fs::metadata(&path).unwrap().len();
+    assert_eq!(len, sample().len() as u64);
+
+    let dat = open_rw(&path);
+    let after = contents(&dat, 0);
+    assert_eq!(after[..2], before.0[..2]);
+    assert_eq!(after[2], replacement);
+    assert_eq!(contents(&dat, 1), before.1);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn replacement_that_does_not_fit_relocates_its_inner_dat() {
+    let path = common::temp_path("relocate.DAT");
+    fs::write(&path, sample()).unwrap();
+    let mut dat = open_rw(&path);
+    let before = (contents(&dat, 0), contents(&dat, 1));
+
+    let replacement = vec![9; 100];
+    let strategy = dat.patch_file(0, 1, &replacement).unwrap();
+    let PatchStrategy::Relocate { new_offset, .. } = strategy else {
+        panic!("expected a relocation, got {strategy}");
+    };
+    assert_eq!(new_offset as usize, sample().len());
+
+    let dat = open_rw(&path);
+    let after = contents(&dat, 0);
+    assert_eq!(after[0], before.0[0]);
+    assert_eq!(after[1], replacement);
+    assert_eq!(after[2], before.0[2]);
+    assert_eq!(contents(&dat, 1), before.1);
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use std::fs;
+
+use baskelian_toolbox::{
+    convert::convert,
+    errors::ValidationError,
+    file::put2d::{Put2D, Put2DEntryType},
+};
+
+const PUT2D: &str =
+    "put2d-script\n007\n0\nC:/dev/ui/title.txd\nC:/dev/ui/title.txt\n2\n5 9 foo bar\n";
+
+#[test]
+fn count_past_the_end_is_a_missing_entry() {
+    assert!(matches!(
+        Put2D::from_data(PUT2D.as_bytes()),
+        Err(ValidationError::MissingToken {
+            line: 8,
+            field: 0,
+            expected: "entry"
+        })
+    ));
+}
+
+#[test]
+fn lenient_parse_keeps_the_entries_that_are_there() {
+    let (put2d_script, diagnostics) = Put2D::from_data_lenient(PUT2D.as_bytes()).unwrap();
+    assert_eq!(put2d_script.entries.len(), 1);
+    assert_eq!(put2d_script.entry_count, 1);
+    assert!(matches!(
+        diagnostics[..],
+        [ValidationError::MissingToken { line: 8, .. }]
+    ));
+    assert_eq!(
+        put2d_script.to_bytes().unwrap(),
+        PUT2D.replace("\n2\n", "\n1\n").as_bytes()
+    );
+}
+
+/// Zero padded numbers, doubled spaces and an unknown entry type, none of which the typed fields can express
+const IRREGULAR: &str = "put2d-script\n007\n0\nC:/dev/ui/title.txd\nC:/dev/ui/title.txt\n03\n0 0 1 2 hello 010 20 1 2 3 4 5 6\n1 1 1 C:/dev/ui/logo.png 1 2 3 4 5 6 7 8 9 logo\n5 9 foo  bar\n\n";
+
+#[test]
+fn unchanged_script_writes_back_byte_for_byte() {
+    let put2d_script = Put2D::from_data(IRREGULAR.as_bytes()).unwrap();
+    assert_eq!(put2d_script.id, 7);
+    assert!(matches!(
+        put2d_script.entries[2].entry_type,
+        Put2DEntryType::UNKNOWN { .. }
+    ));
+    assert_eq!(put2d_script.to_bytes().unwrap(), IRREGULAR.as_bytes());
+}
+
+#[test]
+fn edited_script_keeps_the_text_of_unchanged_fields() {
+    let mut put2d_script = Put2D::from_data(IRREGULAR.as_bytes()).unwrap();
+    put2d_script.txt_path = "C:/dev/ui/other.txt".to_string();
+    put2d_script.entries.pop();
+    assert_eq!(
+        put2d_script.to_bytes().unwrap(),
+        IRREGULAR
+            .replace("title.txt", "other.txt")
+            .replace("\n03\n", "\n2\n")
+            .replace("5 9 foo  bar\n", "")
+            .as_bytes()
+    );
+}
+
+#[test]
+fn documents_convert_back_byte_for_byte() {
+    for extension in ["json", "yaml"] {
+        let script = common::temp_path("script.put2d");
+        let document = common::temp_path(&format!("script.{extension}"));
+        let back = common::temp_path("back.put2d");
+        fs::write(&script, IRREGULAR).unwrap();
+        convert(&script, &document).unwrap();
+        convert(&document, &back).unwrap();
+        assert_eq!(
+            fs::read(&back).unwrap(),
+            IRREGULAR.as_bytes(),
+            "{extension}"
+        );
+        for path in [script, document, back] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
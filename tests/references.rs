@@ -0,0 +1,44 @@
+mod common;
+
+use baskelian_toolbox::dat::references::ReferenceKind;
+use encoding_rs::SHIFT_JIS;
+
+const PUT2D: &str = "put2d-script\n007\n0\nC:/dev/ui/title.txd\nC:/dev/ui/title_text.txt\n1\n1 1 1 C:/dev/ui/logo.png 1 2 3 4 5 6 7 8 9 logo\n";
+
+#[test]
+fn put2d_references_resolve_to_headerless_text() {
+    let text = SHIFT_JIS.encode("タイトル\n").0.into_owned();
+    let dat = common::open(&common::dat(&[common::inner_dat(&[
+        PUT2D.as_bytes(),
+        &common::txd(&["logo"]),
+        &text,
+    ])]));
+    let graph = dat.references();
+    let resolved: Vec<_> = graph
+        .references_from(0, 0)
+        .map(|r| (r.kind, r.resolved.clone()))
+        .collect();
+    assert_eq!(
+        resolved,
+        [
+            (ReferenceKind::TextureDictionary, vec![(0, 1)]),
+            (ReferenceKind::Text, vec![(0, 2)]),
+            (ReferenceKind::Image, vec![(0, 1)]),
+            (ReferenceKind::Texture, vec![(0, 1)]),
+        ]
+    );
+    assert_eq!(graph.dangling().count(), 0);
+    assert!(graph.unreadable.is_empty());
+}
+
+#[test]
+fn malformed_put2d_script_is_unreadable() {
+    let dat = common::open(&common::dat(&[common::inner_dat(&[
+        b"put2d-script\nx\n",
+        &common::txd(&["logo"]),
+    ])]));
+    let graph = dat.references();
+    assert!(graph.references.is_empty());
+    assert_eq!(graph.unreadable.len(), 1);
+    assert_eq!(graph.unreadable[0].file, (0, 0));
+}
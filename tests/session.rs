@@ -0,0 +1,65 @@
+mod common;
+
+use std::fs;
+
+use baskelian_toolbox::dat::DAT;
+
+fn contents(dat: &DAT) -> Vec<Vec<Vec<u8>>> {
+    dat.inner_dats
+        .iter()
+        .map(|inner_dat| {
+            inner_dat
+                .files
+                .iter()
+                .map(|f| f.read_file(dat, inner_dat).unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn committed_edits_read_back() {
+    let source = common::temp_path("source.DAT");
+    let output = common::temp_path("output.DAT");
+    fs::write(
+        &source,
+        common::dat(&[
+            common::inner_dat(&[&common::rw(0x10, &[1; 40]), &common::txd(&["a"]), &[7; 30]]),
+            common::inner_dat(&[&common::txd(&["b"])]),
+        ]),
+    )
+    .unwrap();
+    let dat = DAT::from_file(fs::File::open(&source).unwrap()).unwrap();
+    let mut expected = contents(&dat);
+
+    let mut session = dat.edit();
+    assert!(!session.is_dirty());
+    session.replace(0, 1, vec![9; 5000]).unwrap();
+    session.delete(0, 2).unwrap();
+    session.insert(1, 0, vec![3; 12]).unwrap();
+    assert!(session.is_dirty());
+    session.commit(&output).unwrap();
+    expected[0][1] = vec![9; 5000];
+    expected[0].remove(2);
+    expected[1].insert(0, vec![3; 12]);
+
+    let reopened = DAT::from_file(fs::File::open(&output).unwrap()).unwrap();
+    assert_eq!(contents(&reopened), expected);
+    // an untouched session writes the same files back out
+    reopened.edit().commit(&source).unwrap();
+    let rewritten = DAT::from_file(fs::File::open(&source).unwrap()).unwrap();
+    assert_eq!(contents(&rewritten), expected);
+    fs::remove_file(source).unwrap();
+    fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn rollback_discards_staged_edits() {
+    let dat = common::open(&common::dat(&[common::inner_dat(&[&[7; 30]])]));
+    let mut session = dat.edit();
+    session.delete(0, 0).unwrap();
+    assert!(session.is_dirty());
+    session.rollback();
+    assert!(!session.is_dirty());
+    assert!(session.replace(1, 0, vec![]).is_err());
+}
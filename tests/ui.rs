@@ -0,0 +1,90 @@
+mod common;
+
+use baskelian_toolbox::{
+    dat::{ui::MissingResource, ArchiveType},
+    errors::ArchiveError,
+    file::{detect::DetectorRegistry, txt::Txt, FileType},
+};
+use encoding_rs::SHIFT_JIS;
+
+const PUT2D: &str = "put2d-script\n007\n0\nC:/dev/ui/title.txd\nC:/dev/ui/title_text.txt\n1\n1 1 1 C:/dev/ui/logo.png 1 2 3 4 5 6 7 8 9 logo\n";
+
+fn headerless_text() -> Vec<u8> {
+    SHIFT_JIS.encode("タイトル\nhello world\n").0.into_owned()
+}
+
+#[test]
+fn headerless_text_is_only_text_next_to_a_put2d_script() {
+    let text = headerless_text();
+    assert_eq!(DetectorRegistry::default().detect(&text), FileType::UNKNOWN);
+
+    let dat = common::open(&common::dat(&[common::inner_dat(&[
+        PUT2D.as_bytes(),
+        &common::txd(&["logo"]),
+        &text,
+    ])]));
+    let inner_dat = &dat.inner_dats[0];
+    assert_eq!(inner_dat.files[2].file_type, FileType::TXT);
+    let txt: Txt = inner_dat.files[2].decode(&dat, inner_dat).unwrap();
+    assert_eq!(txt.header, None);
+    assert_eq!(txt.lines, ["タイトル", "hello world"]);
+}
+
+#[test]
+fn put2d_script_is_named_after_its_texture_dictionary() {
+    let dat = common::open(&common::dat(&[common::inner_dat(&[
+        PUT2D.as_bytes(),
+        &common::txd(&["logo"]),
+    ])]));
+    assert_eq!(dat.inner_dats[0].archive_name.as_deref(), Some("title"));
+    assert_eq!(
+        dat.inner_dats[0].files[0].file_name.as_deref(),
+        Some("title")
+    );
+}
+
+#[test]
+fn malformed_put2d_script_is_an_error_rather_than_unnamed() {
+    let dat = common::open(&common::dat(&[common::inner_dat(&[
+        b"put2d-script\nx\n",
+        &common::txd(&["logo"]),
+    ])]));
+    assert_eq!(dat.inner_dats[0].archive_type, ArchiveType::UI);
+    assert_eq!(dat.inner_dats[0].files[0].file_name, None);
+    assert!(matches!(dat.ui(0), Err(ArchiveError::Decode(_))));
+}
+
+#[test]
+fn paths_resolve_to_the_files_they_name() {
+    let dat = common::open(&common::dat(&[common::inner_dat(&[
+        PUT2D.as_bytes(),
+        &common::txd(&["cursor"]),
+        &common::txd(&["logo"]),
+        &headerless_text(),
+    ])]));
+    let ui = dat.ui(0).unwrap();
+    let files = &dat.inner_dats[0].files;
+    assert!(std::ptr::eq(ui.texture().unwrap(), &files[2]));
+    assert!(std::ptr::eq(ui.text_file().unwrap(), &files[3]));
+    assert_eq!(files[3].file_name.as_deref(), Some("title_text"));
+    assert_eq!(ui.text(&dat).unwrap().unwrap().lines[1], "hello world");
+    assert_eq!(ui.missing(&dat), []);
+}
+
+#[test]
+fn every_referenced_path_is_checked() {
+    let dat = common::open(&common::dat(&[common::inner_dat(&[
+        PUT2D.as_bytes(),
+        &common::txd(&["cursor"]),
+    ])]));
+    let ui = dat.ui(0).unwrap();
+    assert!(ui.texture().is_none());
+    assert_eq!(
+        ui.missing(&dat),
+        [
+            MissingResource::Texture("C:/dev/ui/title.txd".to_string()),
+            MissingResource::Text("C:/dev/ui/title_text.txt".to_string()),
+            MissingResource::Image("C:/dev/ui/logo.png".to_string()),
+        ]
+    );
+}